@@ -1,6 +1,7 @@
 use clap::{Parser, Subcommand, Command, Arg};
 use clap::CommandFactory;                  // lets us call Cli::command()
 use directories::ProjectDirs;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::{
     fs,
@@ -8,138 +9,4383 @@ use std::{
     os::unix::fs::PermissionsExt,
     path::{Path, PathBuf},
     process::{Command as Cmd, exit},
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
+/* ---------- diagnostics ---------- */
+
+// set once from --log-file / UNI_LOG_FILE; every diag! line is teed here
+static LOG_FILE: OnceLock<Mutex<fs::File>> = OnceLock::new();
+
+/// `eprintln!` that also appends a timestamped copy to the log file, if any.
+macro_rules! diag {
+    ($($arg:tt)*) => { diag_line(&format!($($arg)*)) };
+}
+
+fn diag_line(msg: &str) {
+    use std::io::Write;
+
+    eprintln!("{msg}");
+    if let Some(file) = LOG_FILE.get() {
+        let mut f = file.lock().unwrap_or_else(|e| e.into_inner());
+        let ts = timestamp();
+        for line in strip_ansi(msg).lines() {
+            let _ = writeln!(f, "{ts} {line}");   // logging must never fail the command
+        }
+    }
+}
+
+// drop `ESC [ … m` color sequences so the log file stays plain text
+fn strip_ansi(msg: &str) -> String {
+    let mut out = String::with_capacity(msg.len());
+    let mut chars = msg.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            for c in chars.by_ref() {
+                if c == 'm' { break; }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Wrap `text` in an SGR color (e.g. `"33"` for yellow) when stderr wants color.
+fn paint(text: &str, sgr: &str) -> String {
+    use std::io::IsTerminal;
+
+    let on = match resolve_color().value {
+        ColorMode::Always => true,
+        ColorMode::Never  => false,
+        ColorMode::Auto   => std::io::stderr().is_terminal(),
+    };
+    if on { format!("\x1b[{sgr}m{text}\x1b[0m") } else { text.to_owned() }
+}
+
+/// Emit one `--progress-json` event: `{"op": .., "step": .., ...fields}`.
+/// Goes straight to stderr (not the log) so a front-end can parse every line.
+fn progress(op: &str, step: &str, fields: serde_json::Value) {
+    if !global_flags().progress_json { return; }
+    let mut event = serde_json::json!({ "op": op, "step": step });
+    if let (Some(e), serde_json::Value::Object(extra)) = (event.as_object_mut(), fields) {
+        e.extend(extra);
+    }
+    eprintln!("{event}");
+}
+
+fn open_log(path: &Path) -> Result<(), IoError> {
+    let f = fs::OpenOptions::new().create(true).append(true).open(path).ctx("opening log file", path)?;
+    let _ = LOG_FILE.set(Mutex::new(f));
+    Ok(())
+}
+
+/// Current UTC time as RFC 3339, e.g. `2025-06-25T14:03:09Z`.
+fn timestamp() -> String {
+    rfc3339(SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0))
+}
+
+fn rfc3339(secs: u64) -> String {
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    // civil-from-days (Howard Hinnant), valid for any date after 1970
+    let z = days as i64 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        rem / 3_600, rem % 3_600 / 60, rem % 60
+    )
+}
+
+/// Name the path (and what was being done to it) in an `io::Error`; the
+/// kind is kept so callers can still match on `NotFound` and friends.
+trait IoContext<T> {
+    fn ctx(self, op: &str, path: &Path) -> Result<T, IoError>;
+}
+
+impl<T> IoContext<T> for Result<T, IoError> {
+    fn ctx(self, op: &str, path: &Path) -> Result<T, IoError> {
+        self.map_err(|e| IoError::new(e.kind(), format!("{op} {}: {e}", path.display())))
+    }
+}
+
 /* ---------- static CLI (built-ins) ---------- */
 
-#[derive(Parser)]
-#[command(name = "uni", version)]
-struct Cli {
-    #[command(subcommand)]
-    command: Option<BuiltIn>,
+#[derive(Parser)]
+#[command(name = "uni", version)]
+struct Cli {
+    #[arg(long, global = true, value_name = "PATH", env = "UNI_LOG_FILE", value_parser = user_path)]
+    log_file: Option<PathBuf>,
+    // refuse anything that needs the network (git sources, installers)
+    #[arg(long, global = true)]
+    offline: bool,
+    #[arg(long, global = true, value_enum)]
+    color: Option<ColorMode>,
+    #[arg(short, long, global = true, conflicts_with = "quiet")]
+    verbose: bool,
+    // drop success messages and notes like "plugin exited with code N"; errors still show
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    // one JSON object per line on stderr for import/export steps
+    #[arg(long, global = true)]
+    progress_json: bool,
+    // mutating commands print only the path they wrote/removed, for `$(…)`
+    #[arg(long, global = true, visible_alias = "print-dir")]
+    porcelain: bool,
+    // which `env_overrides` entry plugins run with
+    #[arg(long, global = true, value_name = "NAME", env = "UNI_PROFILE")]
+    profile: Option<String>,
+    // config file to use instead of the default; must come before the subcommand
+    #[arg(long, value_name = "PATH", value_parser = user_path)]
+    config: Option<PathBuf>,
+    // run as if started in DIR (like `git -C`); must come before the subcommand
+    #[arg(short = 'C', long, value_name = "DIR", value_parser = user_path)]
+    chdir: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<BuiltIn>,
+}
+
+// only ever read through `Cli::command()`, never built, so size is moot
+#[allow(clippy::large_enum_variant)]
+#[derive(Subcommand)]
+enum BuiltIn {
+    Add {
+        #[arg(required_unless_present_any = ["from_git", "from_pypi"], conflicts_with_all = ["from_git", "from_pypi"], value_parser = user_path)]
+        path: Option<PathBuf>,
+        #[arg(long)]
+        pin: bool,
+        #[arg(long, value_name = "URL", requires = "script")]
+        from_git: Option<String>,
+        // script location inside the repo
+        #[arg(long = "path", id = "script", value_name = "PATH")]
+        script: Option<String>,
+        #[arg(long = "ref", id = "git_ref", requires = "from_git")]
+        git_ref: Option<String>,
+        // a PyPI requirement (`pkg`, `pkg==1.2`) whose console script is the plugin
+        #[arg(long, value_name = "PACKAGE", conflicts_with_all = ["from_git", "link"])]
+        from_pypi: Option<String>,
+        // which console script, when the package has several
+        #[arg(long, value_name = "NAME", requires = "from_pypi")]
+        entry_point: Option<String>,
+        #[arg(long, value_name = "SECS", env = "UNI_MANIFEST_TIMEOUT")]
+        manifest_timeout: Option<u64>,
+        // store 0o644 and always launch through `uv run`
+        #[arg(long)]
+        no_exec: bool,
+        // run every check, copy nothing
+        #[arg(long)]
+        validate_only: bool,
+        // like --validate-only, but print where it'd go, the sidecar, and what it replaces
+        #[arg(long, conflicts_with = "validate_only")]
+        dry_run: bool,
+        // once installed, run the first subcommand (or `--help`) and fail if it crashes
+        #[arg(long, conflicts_with_all = ["validate_only", "dry_run"])]
+        smoke_test: bool,
+        // expected SHA-256 (hex) of the script; refuse to install on mismatch
+        #[arg(long, value_name = "SHA256")]
+        checksum: Option<String>,
+        // install as `<name>-2`, `<name>-3`, … instead of replacing a plugin of the same name
+        #[arg(long)]
+        rename_on_collision: bool,
+        // symlink the local script into the plugin dir so edits apply without re-adding
+        #[arg(long, conflicts_with_all = ["from_git", "no_exec"])]
+        link: bool,
+        // added to the manifest's own `tags` (repeatable)
+        #[arg(long = "tag", value_name = "TAG")]
+        tags: Vec<String>,
+        // stored instead of the plugin's own description
+        #[arg(long, value_name = "TEXT")]
+        description: Option<String>,
+        // register a script without the `--manifest` contract as a passthrough plugin
+        #[arg(long)]
+        allow_manifest_failure: bool,
+        // its name; given, `--manifest` isn't even tried (default: the file stem)
+        #[arg(long)]
+        name: Option<String>,
+        // a binary, not a uv script: never asked for `--manifest`, run as-is (no `uv run`)
+        #[arg(long, conflicts_with_all = ["no_exec", "from_git", "from_pypi", "uv_args"])]
+        executable: bool,
+        // install even if some `requires_plugins` aren't installed (warn instead)
+        #[arg(long)]
+        force: bool,
+        // shell command run once the manifest checks out, before anything is copied
+        #[arg(long, value_name = "CMD")]
+        pre: Option<String>,
+        // shell command run after a successful install
+        #[arg(long, value_name = "CMD")]
+        post: Option<String>,
+        // a failing hook only warns (a failing --pre then doesn't stop the install)
+        #[arg(long)]
+        hook_warn_only: bool,
+        // `uv run` option for this plugin, also used for `--manifest` (repeatable)
+        #[arg(long = "uv-arg", value_name = "ARG", allow_hyphen_values = true)]
+        uv_args: Vec<String>,
+        // copy a plugin this replaces to `backups/<name>/<time>/` first; see `uni restore`
+        #[arg(long)]
+        overwrite_backup: bool,
+        // store `key=value` metadata on the installed plugin (repeatable); see `uni set`
+        #[arg(long = "set", value_name = "KEY=VALUE", value_parser = extra_arg)]
+        extra: Vec<(String, String)>,
+        // install even if the plugin's interpreter isn't on this machine (e.g. provisioning another)
+        #[arg(long)]
+        no_interpreter_check: bool,
+        // declare a subcommand the manifest forgot (repeatable); redescribes a declared one
+        #[arg(long = "subcommand", value_name = "NAME:DESC", value_parser = subcommand_arg)]
+        subcommands: Vec<SubCmdMeta>,
+    },
+    Remove {
+        #[arg(required_unless_present = "stdin", conflicts_with = "stdin")]
+        name: Option<String>,
+        #[arg(long)]
+        purge: bool,
+        // list what would be deleted; delete nothing.  Exits 1 if anything
+        // would go, 0 if nothing would
+        #[arg(long)]
+        dry_run: bool,
+        // newline-separated names on stdin (blank lines and `#` comments skipped)
+        #[arg(long)]
+        stdin: bool,
+        // don't ask before a --stdin batch
+        #[arg(long, requires = "stdin")]
+        yes: bool,
+        // carry on past unknown/invalid names; still exits non-zero
+        #[arg(long, requires = "stdin")]
+        keep_going: bool,
+    },
+    List {
+        // annotate each plugin: OK, missing-script, manifest-invalid, uv-too-old, deprecated
+        #[arg(long)]
+        health: bool,
+        // one line per plugin, e.g. "{name}\t{version}"; see LIST_FIELDS
+        #[arg(long, value_name = "TEMPLATE", conflicts_with = "health")]
+        format: Option<String>,
+        // keep plugins whose version is >= / <= this; unparsable ones are reported
+        #[arg(long, value_name = "VERSION", value_parser = version_arg)]
+        min_version: Option<String>,
+        #[arg(long, value_name = "VERSION", value_parser = version_arg)]
+        max_version: Option<String>,
+        // print only how many (valid) plugins match
+        #[arg(long, conflicts_with_all = ["health", "format"])]
+        count: bool,
+        // leave out `hidden` plugins instead of marking them
+        #[arg(long)]
+        no_hidden: bool,
+        // only plugins carrying this tag
+        #[arg(long, value_name = "TAG")]
+        tag: Option<String>,
+        // only plugins whose name or description matches this regex (unanchored)
+        #[arg(long, value_name = "REGEX", value_parser = Regex::new)]
+        grep: Option<Regex>,
+        // only plugins whose script no longer matches the hash recorded at install
+        #[arg(long, conflicts_with_all = ["health", "format", "count"])]
+        modified: bool,
+        // only sidecars that don't parse, as `path:line:column: error`
+        #[arg(long, conflicts_with_all = ["health", "format", "count", "modified"])]
+        broken: bool,
+    },
+    Create {
+        name: String,
+        // write a `<name>.json` sidecar instead of a `--manifest` handler
+        #[arg(long = "static")]
+        static_manifest: bool,
+        // also write a pytest `test_<name>.py` checking the manifest contract
+        #[arg(long)]
+        with_tests: bool,
+    },
+    Export {
+        // default: `export_file` from config, else plugins.<format>
+        #[arg(value_parser = user_path)]
+        file: Option<PathBuf>,
+        #[arg(long, value_enum)]
+        format: Option<ArchiveFormat>,
+        // one archive per plugin, named `<name>.<format>`, instead of a single file
+        #[arg(long, conflicts_with = "file")]
+        split: bool,
+        #[arg(long, value_name = "DIR", requires = "split", default_value = ".", value_parser = user_path)]
+        out_dir: PathBuf,
+        // copy the scripts and sidecars into DIR (created if needed) instead of archiving
+        #[arg(long, value_name = "DIR", value_parser = user_path, conflicts_with_all = ["file", "split", "format"])]
+        to_dir: Option<PathBuf>,
+        // only the `*.json` sidecars: what's installed, without the code
+        #[arg(long, conflicts_with = "split")]
+        manifest_only: bool,
+        // leave out plugins whose name matches this glob (`*`, `?`; repeatable)
+        #[arg(long, value_name = "PATTERN")]
+        exclude: Vec<String>,
+    },
+    Import {
+        // a zip or tar.gz archive, or a directory from `export --to-dir`
+        #[arg(value_parser = user_path, required_unless_present = "from_url", conflicts_with = "from_url")]
+        file: Option<PathBuf>,
+        // download the archive first (refused under --offline)
+        #[arg(long, value_name = "URL")]
+        from_url: Option<String>,
+        // expected SHA-256 (hex) of the archive
+        #[arg(long, value_name = "SHA256")]
+        checksum: Option<String>,
+        #[arg(long, value_enum, default_value = "replace")]
+        overwrite_policy: OverwritePolicy,
+        // zip only: read entries straight from the archive; only the script
+        // under validation ever touches disk, one at a time
+        #[arg(long)]
+        in_memory: bool,
+        // copy each script and its archived sidecar as they are, without
+        // re-checking the manifest or its dependencies
+        #[arg(long)]
+        no_validate: bool,
+    },
+    EnsurePython {
+        #[arg(long)]
+        force: bool,
+    },
+    // re-run every installed plugin's `--manifest` and report the ones that now fail
+    ValidateAll {
+        // several plugins at a time (output is still in name order)
+        #[arg(long)]
+        parallel: bool,
+    },
+    Freeze,
+    Sync {
+        #[arg(value_parser = user_path)]
+        lockfile: PathBuf,
+        #[arg(long)]
+        prune: bool,
+        #[arg(long)]
+        dry_run: bool,
+        #[arg(long)]
+        force: bool,
+    },
+    Info {
+        name: String,
+        #[arg(long, conflicts_with = "history")]
+        raw: bool,
+        // list the versions kept for `uni rollback`
+        #[arg(long)]
+        history: bool,
+    },
+    Diff {
+        name: String,
+        #[arg(value_parser = user_path)]
+        script: PathBuf,
+    },
+    Pin   { name: String },
+    Unpin { name: String },
+    // attach `key=value` metadata to an installed plugin; see `Manifest::extra`
+    Set {
+        name: String,
+        #[arg(value_name = "KEY=VALUE", required = true, value_parser = extra_arg)]
+        pairs: Vec<(String, String)>,
+    },
+    Unset {
+        name: String,
+        #[arg(value_name = "KEY", required = true)]
+        keys: Vec<String>,
+    },
+    // restore the version replaced by the last add/import/sync
+    Rollback { name: String },
+    // carry a plugin (script, sidecar, assets, history, backups) to another plugin dir
+    Move {
+        name: String,
+        #[arg(value_parser = user_path)]
+        dir: PathBuf,
+    },
+    // bring back a copy kept by `add --overwrite-backup` (default: the newest)
+    Restore {
+        name: String,
+        // show the kept copies instead
+        #[arg(long)]
+        list: bool,
+        #[arg(long, value_name = "TIME", conflicts_with = "list")]
+        from: Option<String>,
+    },
+    Config,
+    Schema,
+    Bench {
+        name: String,
+        subcommand: Option<String>,
+        #[arg(long, default_value_t = 5)]
+        runs: usize,
+        #[arg(long)]
+        json: bool,
+        // forwarded to the plugin as is, after `--`; `--runs`/`--json` there are the plugin's
+        #[arg(last = true)]
+        args: Vec<std::ffi::OsString>,
+    },
+    // called by the shell hook on every <TAB>; see `--script`
+    #[command(name = "__complete", hide = true)]
+    Complete {
+        // print the hook to source for this shell instead of completing
+        #[arg(long, value_parser = ["bash", "zsh", "fish"])]
+        script: Option<String>,
+        // the words after `uni`; the last one is being completed (may be "")
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        words: Vec<String>,
+    },
+    // interactive prompt: type `deploy run` instead of `uni deploy run`
+    Shell,
+    // run `script` through the plugin protocol and print a pass/fail checklist
+    Selftest {
+        #[arg(value_parser = user_path)]
+        script: PathBuf,
+        #[arg(long, value_name = "SECS", default_value_t = 10)]
+        timeout: u64,
+    },
+    // vet a script without running it: PEP 723 header, imports, static manifest
+    Inspect {
+        #[arg(value_parser = user_path)]
+        script: PathBuf,
+    },
+    // counts of what's installed
+    Stats {
+        // per-plugin disk usage (script, sidecar, assets, data), largest first
+        #[arg(long)]
+        disk: bool,
+    },
+    // show `requires_plugins` edges; fails on missing plugins or cycles
+    Graph {
+        // Graphviz DOT instead of a text tree
+        #[arg(long)]
+        dot: bool,
+    },
+    // run a plugin from a JSON request, answer with JSON; see `call_plugin`
+    Call {
+        name: String,
+        #[arg(value_name = "JSON")]
+        request: String,
+    },
+    // delete scripts without a sidecar and sidecars without a script
+    Prune {
+        // list what would be deleted; delete nothing
+        #[arg(long)]
+        dry_run: bool,
+        // don't ask first
+        #[arg(long)]
+        yes: bool,
+    },
+    // show the record of add/remove/import/export/…; see `audit_path`
+    Audit {
+        // only these operations (repeatable)
+        #[arg(long = "op", value_name = "OP", value_parser = AUDITED_OPS.to_vec())]
+        ops: Vec<String>,
+        // only the last N entries
+        #[arg(long, value_name = "N")]
+        tail: Option<usize>,
+        // the raw JSON lines
+        #[arg(long)]
+        json: bool,
+    },
+    Doctor {
+        #[arg(long)]
+        fix: bool,
+        // don't prompt before destructive fixes
+        #[arg(long, requires = "fix")]
+        yes: bool,
+        // leave a check out entirely: not run, not reported, not fixed (repeatable)
+        #[arg(long, value_name = "CHECK", value_parser = ["plugin-dir", "uv", "orphans", "exec-bit", "collisions", "links", "modified", "network-fs", "manifests"])]
+        skip: Vec<String>,
+        // one JSON report (`ok`, plus each check's status and message); never prompts
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/* ---------- path arguments ---------- */
+
+/// Expand a leading `~` and `$VAR` / `${VAR}` references the way a shell would,
+/// for callers (IDEs, exec wrappers, quoted args) that hand us the raw text.
+/// Unset variables are left as written.
+fn expand_user_path(raw: &str) -> PathBuf {
+    let home = || directories::BaseDirs::new().map(|b| b.home_dir().to_string_lossy().into_owned());
+    let mut out = String::new();
+    let mut rest = raw;
+
+    if (rest == "~" || rest.starts_with("~/") || rest.starts_with("~\\"))
+        && let Some(h) = home()
+    {
+        out.push_str(&h);
+        rest = &rest[1..];
+    }
+    while let Some(i) = rest.find('$') {
+        out.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        let (var, tail) = match after.strip_prefix('{') {
+            Some(braced) => match braced.find('}') {
+                Some(end) => (&braced[..end], &braced[end + 1..]),
+                None => ("", after),
+            },
+            None => {
+                let end = after.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(after.len());
+                (&after[..end], &after[end..])
+            }
+        };
+        match std::env::var(var) {
+            Ok(val) if !var.is_empty() => out.push_str(&val),
+            _ => out.push_str(&rest[i..rest.len() - tail.len()]),
+        }
+        rest = tail;
+    }
+    out.push_str(rest);
+    PathBuf::from(out)
+}
+
+// clap value_parser for every user-supplied path argument
+fn user_path(raw: &str) -> Result<PathBuf, std::convert::Infallible> {
+    Ok(expand_user_path(raw))
+}
+
+/* ---------- manifest ---------- */
+
+#[derive(Serialize, Deserialize, Clone, schemars::JsonSchema)]
+struct SubCmdMeta {
+    name: String,
+    description: String,
+    // typed arguments for clap to check; absent = free-form trailing args
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(description = "Typed arguments checked by uni; omit to forward anything")]
+    args: Option<Vec<ArgSpec>>,
+}
+
+/// `add --subcommand NAME:DESCRIPTION`; the description may be empty.
+fn subcommand_arg(raw: &str) -> Result<SubCmdMeta, String> {
+    let (name, description) = raw.split_once(':').unwrap_or((raw, ""));
+    if name.is_empty() {
+        return Err("expected NAME:DESCRIPTION".into());
+    }
+    Ok(SubCmdMeta { name: name.to_owned(), description: description.to_owned(), args: None })
+}
+
+/// `--set KEY=VALUE` / `uni set`: the value may be empty or contain `=`.
+fn extra_arg(raw: &str) -> Result<(String, String), String> {
+    match raw.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => Ok((key.trim().to_owned(), value.to_owned())),
+        _ => Err("expected KEY=VALUE".into()),
+    }
+}
+
+/// One declared subcommand argument.  Positional unless `long` (bools are
+/// always `--name` switches); forwarded to the plugin in declaration order.
+#[derive(Serialize, Deserialize, Clone, schemars::JsonSchema)]
+struct ArgSpec {
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    required: bool,
+    #[serde(default, rename = "type")]
+    #[schemars(description = "`bool` arguments are `--name` switches")]
+    kind: ArgKind,
+    #[serde(default)]
+    #[schemars(description = "Pass as `--name VALUE` instead of positionally")]
+    long: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum ArgKind {
+    #[default]
+    String,
+    Int,
+    Float,
+    Bool,
+    Path,
+}
+
+impl ArgSpec {
+    fn is_switch(&self) -> bool {
+        self.kind == ArgKind::Bool
+    }
+
+    fn is_positional(&self) -> bool {
+        !self.long && !self.is_switch()
+    }
+
+    fn to_arg(&self) -> Arg {
+        let arg = Arg::new(self.name.clone()).help(self.description.clone());
+        if self.is_switch() {
+            return arg.long(self.name.clone()).action(clap::ArgAction::SetTrue);
+        }
+        let arg = arg.required(self.required);
+        let arg = if self.long { arg.long(self.name.clone()) } else { arg };
+        match self.kind {
+            ArgKind::Int   => arg.value_parser(clap::value_parser!(i64)),
+            ArgKind::Float => arg.value_parser(clap::value_parser!(f64)),
+            ArgKind::Path  => arg.value_parser(clap::value_parser!(PathBuf)),
+            ArgKind::String | ArgKind::Bool => arg,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, schemars::JsonSchema)]
+#[schemars(title = "uni plugin manifest")]
+struct Manifest {
+    #[schemars(description = "Subcommand name; also the installed file name")]
+    name: String,
+    description: String,
+    version: String,
+    #[serde(default)]
+    commands: Vec<SubCmdMeta>,
+    // where the script was installed from; written by `uni`, not the plugin
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip)]
+    source: Option<String>,
+    // pinned plugins are skipped by `sync` unless --force
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[schemars(skip)]
+    pinned: bool,
+    // user metadata (owner, ticket, …) from `add --set` / `uni set`; uni never reads it
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    #[schemars(skip)]
+    extra: std::collections::BTreeMap<String, String>,
+    // ask the plugin (`<script> --commands`) instead of trusting `commands`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[schemars(description = "Ask the script for its subcommands via `--commands` at CLI-build time")]
+    dynamic_commands: bool,
+    // forward undeclared subcommands to the script instead of rejecting them
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[schemars(description = "Forward undeclared subcommands to the script instead of rejecting them")]
+    allow_any_subcommand: bool,
+    // stored without the executable bit; dispatched via `uv run`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[schemars(skip)]
+    no_exec: bool,
+    // SHA-256 of the installed script, for `list --modified`; unset for links
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(skip)]
+    sha256: Option<String>,
+    // the plugin-dir entry is a symlink to the source (`add --link`)
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[schemars(skip)]
+    linked: bool,
+    // `description` came from `add --description`; reinstalls keep it
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[schemars(skip)]
+    custom_description: bool,
+    // extra names the plugin answers to (`uni d` for `uni deploy`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(description = "Other names the plugin can be invoked by; must not collide with any other plugin or built-in")]
+    aliases: Vec<String>,
+    // free-form labels for `list --tag`; `add --tag` appends to them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(description = "Labels for `uni list --tag`")]
+    tags: Vec<String>,
+    // runnable, but left out of `uni --help` and completion
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[schemars(description = "Keep the plugin out of `uni --help`; it still runs")]
+    hidden: bool,
+    // sunset notice (e.g. "use `deploy2` instead"), printed before every run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(description = "Deprecation notice shown on stderr before every run")]
+    deprecated: Option<String>,
+    // extra environment for the child process
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    #[schemars(description = "Environment variables set for every run")]
+    env: std::collections::BTreeMap<String, String>,
+    // per-profile env merged over `env` when `--profile`/UNI_PROFILE names one
+    #[serde(default, skip_serializing_if = "std::collections::BTreeMap::is_empty")]
+    #[schemars(description = "Profile name → variables merged over `env` when uni runs with --profile/UNI_PROFILE")]
+    env_overrides: std::collections::BTreeMap<String, std::collections::BTreeMap<String, String>>,
+    // oldest `uni` that understands this manifest; checked at add and run time
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(description = "Oldest uni release the plugin works with, e.g. \"0.3.0\"")]
+    min_uni_version: Option<String>,
+    // other plugins (by name) this one calls; see `uni graph`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(description = "Names of other plugins this one invokes")]
+    requires_plugins: Vec<String>,
+    // sample command lines, shown at the end of `uni <name> --help`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(description = "Sample command lines (e.g. \"uni deploy run --env prod\") listed under `uni <name> --help`")]
+    examples: Vec<String>,
+    // program the script runs under (`node`, `bash`, …); unset: the shebang's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(description = "Program the script needs (e.g. \"node\"), checked by `uni add`; defaults to the shebang's")]
+    interpreter: Option<String>,
+    // inserted as `uv run <uv_args> <script>` wherever uni goes through uv
+    // (`--manifest`, `--commands`, no_exec dispatch); the shebang path ignores them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schemars(description = "Extra `uv run` options (e.g. [\"--no-project\"]) used whenever uni runs the script through uv")]
+    uv_args: Vec<String>,
+    // how the manifest was obtained at install; written by `uni`
+    #[serde(default, skip_serializing_if = "ManifestOrigin::is_exec")]
+    #[schemars(skip)]
+    manifest_origin: ManifestOrigin,
+    // a raw executable (`add --executable`): exec'd directly, shebang left alone
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    #[schemars(skip)]
+    executable: bool,
+}
+
+/// Where `validate-all` can find a plugin's manifest again.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum ManifestOrigin {
+    // printed by `<script> --manifest`
+    #[default]
+    Exec,
+    // a `<script>.json` next to the source
+    Sidecar,
+    // `add --allow-manifest-failure`: made up by uni
+    None,
+}
+
+impl ManifestOrigin {
+    fn is_exec(&self) -> bool {
+        *self == ManifestOrigin::Exec
+    }
+}
+
+/// JSON Schema (draft 2020-12) for what a plugin prints on `--manifest`,
+/// derived from the structs above; the fields uni writes itself are skipped.
+fn manifest_schema() -> serde_json::Value {
+    schemars::schema_for!(Manifest).to_value()
+}
+
+/* ---------- versions ---------- */
+
+/// Compare `MAJOR.MINOR.PATCH[-pre]` style versions numerically; missing
+/// components count as 0 and a pre-release sorts before its release.  `None`
+/// when either side isn't made of numbers (e.g. `"dev"`).
+fn cmp_versions(a: &str, b: &str) -> Option<std::cmp::Ordering> {
+    fn parse(v: &str) -> Option<(Vec<u64>, Option<&str>)> {
+        let v = v.trim().trim_start_matches('v');
+        let v = v.split_once('+').map_or(v, |(core, _build)| core);
+        let (core, pre) = match v.split_once('-') {
+            Some((c, p)) => (c, Some(p)),
+            None => (v, None),
+        };
+        let nums = core.split('.').map(|n| n.parse().ok()).collect::<Option<Vec<u64>>>()?;
+        Some((nums, pre))
+    }
+    let (mut an, apre) = parse(a)?;
+    let (mut bn, bpre) = parse(b)?;
+    let len = an.len().max(bn.len());
+    an.resize(len, 0);
+    bn.resize(len, 0);
+    Some(an.cmp(&bn).then_with(|| match (apre, bpre) {
+        (None, None) => std::cmp::Ordering::Equal,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (Some(x), Some(y)) => x.cmp(y),
+    }))
+}
+
+fn version_arg(raw: &str) -> Result<String, String> {
+    match cmp_versions(raw, raw) {
+        Some(_) => Ok(raw.to_owned()),
+        None => Err(format!("`{raw}` is not a version like 1.2.3")),
+    }
+}
+
+/// Inclusive bounds from `list --min-version/--max-version`; either may be open.
+struct VersionRange {
+    min: Option<String>,
+    max: Option<String>,
+}
+
+impl VersionRange {
+    fn is_open(&self) -> bool {
+        self.min.is_none() && self.max.is_none()
+    }
+
+    /// `None` when `version` can't be compared at all.
+    fn contains(&self, version: &str) -> Option<bool> {
+        if self.is_open() {
+            return Some(true);
+        }
+        let above = match &self.min {
+            Some(min) => cmp_versions(version, min)?.is_ge(),
+            None => true,
+        };
+        let below = match &self.max {
+            Some(max) => cmp_versions(version, max)?.is_le(),
+            None => true,
+        };
+        Some(above && below)
+    }
+}
+
+/* ---------- lockfile ---------- */
+
+#[derive(Serialize, Deserialize)]
+struct LockEntry {
+    name: String,
+    version: String,
+    source: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Lockfile {
+    plugins: Vec<LockEntry>,
+}
+
+/* ---------- config ---------- */
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, clap::ValueEnum)]
+enum ArchiveFormat {
+    #[serde(rename = "zip")]
+    #[value(name = "zip")]
+    Zip,
+    #[serde(rename = "tar.gz")]
+    #[value(name = "tar.gz")]
+    TarGz,
+}
+
+impl ArchiveFormat {
+    fn from_path(p: &Path) -> Option<Self> {
+        let name = p.file_name()?.to_string_lossy();
+        if name.ends_with(".zip") {
+            Some(Self::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(Self::TarGz)
+        } else {
+            None
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Zip   => "zip",
+            Self::TarGz => "tar.gz",
+        }
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+/// `~/.config/mycli/config.json`; every field is optional.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Config {
+    plugin_dir: Option<PathBuf>,
+    python_version: Option<String>,
+    export_file: Option<PathBuf>,
+    export_format: Option<ArchiveFormat>,
+    // default for `uni add --no-exec`
+    no_exec: Option<bool>,
+    offline: Option<bool>,
+    color: Option<ColorMode>,
+    // toolchain binaries, by name on PATH or absolute path
+    uv_bin: Option<PathBuf>,
+    python_bin: Option<PathBuf>,
+    // JSON-Lines record of mutating commands; see `uni audit`
+    audit_file: Option<PathBuf>,
+}
+
+// `--config`, found by `leading_flags` before clap runs: the config decides
+// the plugin dir, and with it which subcommands exist
+static CONFIG_FLAG: OnceLock<Option<PathBuf>> = OnceLock::new();
+
+fn config_path() -> PathBuf {
+    resolve_config_path().value
+}
+
+fn resolve_config_path() -> Resolved<PathBuf> {
+    if let Some(Some(p)) = CONFIG_FLAG.get() {
+        return Resolved { value: p.clone(), origin: Origin::Flag };
+    }
+    if let Some(v) = std::env::var_os("UNI_CONFIG").filter(|v| !v.is_empty()) {
+        return Resolved { value: expand_user_path(&v.to_string_lossy()), origin: Origin::Env("UNI_CONFIG") };
+    }
+    let default = ProjectDirs::from("", "", "mycli")
+        .expect("cannot determine config dir")
+        .config_dir()
+        .join("config.json");
+    Resolved { value: default, origin: Origin::Default }
+}
+
+// long flags that take a value, so their value isn't mistaken for the subcommand
+const VALUED_GLOBALS: &[&str] = &["--log-file", "--color", "--profile", "--config", "--chdir"];
+// the only short flag that takes one: `-C DIR`, `-CDIR`, `-C=DIR`, `-vC DIR`
+const CHDIR_SHORT: char = 'C';
+
+/// Does `word` (before the subcommand) take the next word as its value?
+fn takes_next(word: &str) -> bool {
+    match word.strip_prefix("--") {
+        Some(_) => VALUED_GLOBALS.contains(&word),
+        // in a short cluster everything after `C` is its value
+        None => word.strip_prefix('-').and_then(|s| s.find(CHDIR_SHORT).map(|i| i + 1 == s.len())).unwrap_or(false),
+    }
+}
+
+/// The flags that must act before clap runs, read from the words before the
+/// subcommand.  `run` checks them against clap's own parse.
+#[derive(Default)]
+struct LeadingFlags {
+    config: Option<PathBuf>,
+    chdir: Option<PathBuf>,
+}
+
+/// One pass over the leading words, taking every form clap accepts:
+/// `--config PATH`, `--config=PATH`, `--chdir DIR`, `-C DIR`, `-CDIR`,
+/// `-C=DIR` and short clusters such as `-vC DIR`.  Last one wins, as in clap.
+fn leading_flags(args: &[std::ffi::OsString]) -> LeadingFlags {
+    let mut flags = LeadingFlags::default();
+    let mut words = args.iter().skip(1).map(|a| a.to_string_lossy());
+    while let Some(w) = words.next() {
+        if w == "--" || w == "-" || !w.starts_with('-') {
+            break;
+        }
+        let (name, value) = if w.starts_with("--") {
+            match w.split_once('=') {
+                Some((name, v)) => (name.to_owned(), Some(v.to_owned())),
+                None if takes_next(&w) => (w.to_string(), words.next().map(|v| v.into_owned())),
+                None => (w.to_string(), None),
+            }
+        } else if let Some(i) = w[1..].find(CHDIR_SHORT) {
+            let rest = &w[2 + i..];
+            let rest = rest.strip_prefix('=').unwrap_or(rest);
+            let value = if rest.is_empty() { words.next().map(|v| v.into_owned()) } else { Some(rest.to_owned()) };
+            ("--chdir".to_owned(), value)
+        } else {
+            continue;
+        };
+        let value = value.map(|v| expand_user_path(&v));
+        match name.as_str() {
+            "--config" => flags.config = value,
+            "--chdir" => flags.chdir = value,
+            _ => {}
+        }
+    }
+    flags
+}
+
+/// Index of the `@<name>` word in `uni [global flags] @<name> args…`.  Found
+/// before clap runs, so whatever follows reaches the plugin untouched and a
+/// built-in or alias of the same name can't get in the way.
+fn at_plugin(args: &[std::ffi::OsString]) -> Option<usize> {
+    let mut i = 1;
+    while let Some(w) = args.get(i).map(|a| a.to_string_lossy()) {
+        if w.starts_with('@') {
+            return Some(i);
+        }
+        if w == "--" || !w.starts_with('-') {
+            return None;
+        }
+        i += if takes_next(&w) { 2 } else { 1 };
+    }
+    None
+}
+
+fn config() -> &'static Config {
+    static CONFIG: OnceLock<Config> = OnceLock::new();
+    CONFIG.get_or_init(|| {
+        let path = config_path();
+        let Ok(bytes) = fs::read(&path) else { return Config::default() };
+        serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+            diag!("⚠️  ignoring {}: {e}", path.display());
+            Config::default()
+        })
+    })
+}
+
+/* ---------- settings resolution ---------- */
+
+// precedence, highest first: flag, environment, config file, built-in default
+#[derive(Clone, Copy)]
+enum Origin {
+    Flag,
+    Env(&'static str),
+    Config,
+    Default,
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Origin::Flag     => write!(f, "flag"),
+            Origin::Env(var) => write!(f, "env {var}"),
+            Origin::Config   => write!(f, "config {}", config_path().display()),
+            Origin::Default  => write!(f, "default"),
+        }
+    }
+}
+
+struct Resolved<T> {
+    value: T,
+    origin: Origin,
+}
+
+/// Global flags that feed settings resolution; set once in `run` after parsing.
+struct GlobalFlags {
+    offline: bool,
+    color: Option<ColorMode>,
+    verbose: bool,
+    quiet: bool,
+    progress_json: bool,
+    porcelain: bool,
+    profile: Option<String>,
+}
+
+static GLOBAL_FLAGS: OnceLock<GlobalFlags> = OnceLock::new();
+
+// build_cli runs before parsing, so this must not lock in the defaults early
+fn global_flags() -> &'static GlobalFlags {
+    static UNSET: GlobalFlags = GlobalFlags {
+        offline: false, color: None, verbose: false, quiet: false, progress_json: false, porcelain: false,
+        profile: None,
+    };
+    GLOBAL_FLAGS.get().unwrap_or(&UNSET)
+}
+
+const DEFAULT_PYTHON_VERSION: &str = "3.13.3";
+
+// resolved before argument parsing (build_cli needs it), so there is no flag
+fn resolve_plugin_dir() -> Resolved<PathBuf> {
+    if let Some(dir) = std::env::var("UNI_PLUGIN_DIR").ok().filter(|d| !d.is_empty()) {
+        return Resolved { value: expand_user_path(&dir), origin: Origin::Env("UNI_PLUGIN_DIR") };
+    }
+    if let Some(dir) = &config().plugin_dir {
+        return Resolved { value: expand_user_path(&dir.to_string_lossy()), origin: Origin::Config };
+    }
+    let dir = ProjectDirs::from("", "", "mycli")
+        .expect("cannot determine config dir")
+        .config_dir()
+        .join("plugins");
+    Resolved { value: dir, origin: Origin::Default }
+}
+
+fn resolve_python_version() -> Resolved<String> {
+    if let Ok(v) = std::env::var("UNI_PYTHON_VERSION") {
+        return Resolved { value: v, origin: Origin::Env("UNI_PYTHON_VERSION") };
+    }
+    match &config().python_version {
+        Some(v) => Resolved { value: v.clone(), origin: Origin::Config },
+        None    => Resolved { value: DEFAULT_PYTHON_VERSION.into(), origin: Origin::Default },
+    }
+}
+
+fn resolve_uv_bin() -> Resolved<PathBuf> {
+    if let Some(v) = std::env::var_os("UNI_UV_BIN").filter(|v| !v.is_empty()) {
+        return Resolved { value: expand_user_path(&v.to_string_lossy()), origin: Origin::Env("UNI_UV_BIN") };
+    }
+    match &config().uv_bin {
+        Some(p) => Resolved { value: p.clone(), origin: Origin::Config },
+        None    => Resolved { value: "uv".into(), origin: Origin::Default },
+    }
+}
+
+// unset means "probe python3, then python"
+fn resolve_python_bin() -> Option<Resolved<PathBuf>> {
+    if let Some(v) = std::env::var_os("UNI_PYTHON_BIN").filter(|v| !v.is_empty()) {
+        return Some(Resolved { value: expand_user_path(&v.to_string_lossy()), origin: Origin::Env("UNI_PYTHON_BIN") });
+    }
+    config().python_bin.clone().map(|p| Resolved { value: p, origin: Origin::Config })
+}
+
+fn resolve_audit_file() -> Resolved<PathBuf> {
+    if let Some(v) = std::env::var_os("UNI_AUDIT_FILE").filter(|v| !v.is_empty()) {
+        return Resolved { value: expand_user_path(&v.to_string_lossy()), origin: Origin::Env("UNI_AUDIT_FILE") };
+    }
+    if let Some(p) = &config().audit_file {
+        return Resolved { value: p.clone(), origin: Origin::Config };
+    }
+    let default = ProjectDirs::from("", "", "mycli")
+        .expect("cannot determine data dir")
+        .data_dir()
+        .join("audit.jsonl");
+    Resolved { value: default, origin: Origin::Default }
+}
+
+/// A `uv` invocation, honouring UNI_UV_BIN / `uv_bin`.
+fn uv() -> Cmd {
+    Cmd::new(resolve_uv_bin().value)
+}
+
+/// `uv run <uv_args> <script>`; arguments for the script go after.
+fn uv_run(uv_args: &[String], script: &Path) -> Cmd {
+    let mut cmd = uv();
+    cmd.arg("run").args(uv_args).arg(script);
+    cmd
+}
+
+fn resolve_export_format() -> Resolved<ArchiveFormat> {
+    match config().export_format {
+        Some(f) => Resolved { value: f, origin: Origin::Config },
+        None    => Resolved { value: ArchiveFormat::Zip, origin: Origin::Default },
+    }
+}
+
+fn resolve_offline() -> Resolved<bool> {
+    if global_flags().offline {
+        return Resolved { value: true, origin: Origin::Flag };
+    }
+    if let Ok(v) = std::env::var("UNI_OFFLINE") {
+        return Resolved { value: !matches!(v.as_str(), "" | "0" | "false"), origin: Origin::Env("UNI_OFFLINE") };
+    }
+    match config().offline {
+        Some(v) => Resolved { value: v, origin: Origin::Config },
+        None    => Resolved { value: false, origin: Origin::Default },
+    }
+}
+
+fn resolve_color() -> Resolved<ColorMode> {
+    if let Some(c) = global_flags().color {
+        return Resolved { value: c, origin: Origin::Flag };
+    }
+    // https://no-color.org: any non-empty value disables color
+    if std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+        return Resolved { value: ColorMode::Never, origin: Origin::Env("NO_COLOR") };
+    }
+    match config().color {
+        Some(c) => Resolved { value: c, origin: Origin::Config },
+        None    => Resolved { value: ColorMode::Auto, origin: Origin::Default },
+    }
+}
+
+fn verbose() -> bool {
+    global_flags().verbose
+}
+
+fn quiet() -> bool {
+    global_flags().quiet
+}
+
+fn porcelain() -> bool {
+    global_flags().porcelain
+}
+
+/// The one stdout line of a mutating command: `friendly`, just `path` under
+/// `--porcelain`, nothing under `--quiet`.
+fn report(path: &Path, friendly: impl std::fmt::Display) {
+    if porcelain() {
+        println!("{}", path.display());
+    } else {
+        say(friendly);
+    }
+}
+
+/// A progress/summary line of a mutating command; dropped by `--quiet` and
+/// `--porcelain` alike.  Errors and warnings go through `diag!` instead.
+fn say(msg: impl std::fmt::Display) {
+    if !quiet() && !porcelain() {
+        println!("{msg}");
+    }
+}
+
+fn offline() -> bool {
+    resolve_offline().value
+}
+
+fn require_online(what: &str) -> Result<(), Box<dyn std::error::Error>> {
+    if offline() {
+        return Err(format!("{what} needs the network, but uni is in offline mode").into());
+    }
+    Ok(())
+}
+
+fn show_config() {
+    let row = |key: &str, value: String, origin: Origin| println!("{key:<16} {value:<40} ({origin})");
+
+    let file = resolve_config_path();
+    let missing = if file.value.is_file() { "" } else { " (missing)" };
+    row("config", format!("{}{missing}", file.value.display()), file.origin);
+
+    let dir = resolve_plugin_dir();
+    row("plugin_dir", dir.value.display().to_string(), dir.origin);
+    let py = resolve_python_version();
+    row("python_version", py.value, py.origin);
+    let fmt = resolve_export_format();
+    row("export_format", fmt.value.extension().into(), fmt.origin);
+    let off = resolve_offline();
+    row("offline", off.value.to_string(), off.origin);
+    let color = resolve_color();
+    let color_name = match color.value {
+        ColorMode::Auto   => "auto",
+        ColorMode::Always => "always",
+        ColorMode::Never  => "never",
+    };
+    row("color", color_name.into(), color.origin);
+    let uv = resolve_uv_bin();
+    row("uv_bin", uv.value.display().to_string(), uv.origin);
+    match resolve_python_bin() {
+        Some(py) => row("python_bin", py.value.display().to_string(), py.origin),
+        None     => row("python_bin", "python3, python".into(), Origin::Default),
+    }
+    let audit = resolve_audit_file();
+    row("audit_file", audit.value.display().to_string(), audit.origin);
+}
+
+/* ---------- plugin-directory helpers ---------- */
+
+fn plugin_dir() -> PathBuf {
+    resolve_plugin_dir().value
+}
+
+fn cache_dir() -> PathBuf {
+    ProjectDirs::from("", "", "mycli")
+        .expect("cannot determine cache dir")
+        .cache_dir()
+        .to_path_buf()
+}
+
+fn ensure_plugin_dir() -> Result<(), IoError> {
+    let dir = plugin_dir();
+    fs::create_dir_all(&dir).ctx("creating plugin dir", &dir)
+}
+
+/// The kind of network filesystem `path` lives on, if any.  Every invocation
+/// reads the whole plugin dir, so this is where startup latency comes from.
+#[cfg(target_os = "linux")]
+fn network_fs(path: &Path) -> Option<&'static str> {
+    use std::os::unix::ffi::OsStrExt;
+    let c = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut st: libc::statfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statfs(c.as_ptr(), &mut st) } != 0 {
+        return None;
+    }
+    // magic numbers from statfs(2); FUSE covers sshfs and most sync clients
+    match st.f_type as u32 {
+        0x6969 => Some("NFS"),
+        0x517b | 0xff53_4d42 | 0xfe53_4d42 => Some("SMB/CIFS"),
+        0x5346_414f => Some("AFS"),
+        0x00c3_6400 => Some("Ceph"),
+        0x0102_1997 => Some("9p"),
+        0x6573_5546 => Some("FUSE"),
+        _ => None,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn network_fs(_path: &Path) -> Option<&'static str> {
+    None
+}
+
+fn network_fs_warning(dir: &Path, kind: &str) -> String {
+    format!(
+        "plugin dir {} is on {kind}, which may slow down every `uni` invocation; \
+        consider a local dir via UNI_PLUGIN_DIR",
+        dir.display()
+    )
+}
+
+/// Warn about a network plugin dir once per dir, not on every run; the last
+/// dir checked is remembered in the cache.
+fn warn_network_fs_once() {
+    let dir = plugin_dir();
+    let marker = cache_dir().join("fs-checked");
+    if fs::read(&marker).is_ok_and(|b| b == dir.as_os_str().as_encoded_bytes()) {
+        return;
+    }
+    if let Some(kind) = network_fs(&dir) {
+        diag!("⚠️  {} (shown once; `uni doctor` repeats it)", network_fs_warning(&dir, kind));
+    }
+    let _ = fs::create_dir_all(cache_dir());
+    let _ = fs::write(&marker, dir.as_os_str().as_encoded_bytes());
+}
+
+// `<name>` itself is the script, so a plugin's bundled files live next to it
+fn asset_dir(name: &str) -> PathBuf {
+    plugin_dir().join(format!("{name}.d"))
+}
+
+// runtime state the plugin keeps outside the plugin dir; only removed on --purge
+fn data_dir(name: &str) -> PathBuf {
+    ProjectDirs::from("", "", "mycli")
+        .expect("cannot determine data dir")
+        .data_dir()
+        .join("plugins")
+        .join(name)
+}
+
+/* ---------- add / remove / list ---------- */
+
+/// Per-install knobs for `validate_and_copy`.
+#[derive(Default, Clone)]
+struct InstallOpts {
+    /// Recorded in the sidecar so `freeze`/`sync` can reinstall the plugin.
+    source: Option<String>,
+    /// Pin the plugin; an existing pin survives a reinstall either way.
+    pin: bool,
+    /// How long `--manifest` may run; `DEFAULT_MANIFEST_TIMEOUT` if unset.
+    manifest_timeout: Option<Duration>,
+    /// Store the script `0o644`; see `Manifest::no_exec`.
+    no_exec: bool,
+    /// Stop after validation; `plugin_dir()` is never touched.
+    validate_only: bool,
+    /// Expected SHA-256 of the script bytes, checked before anything runs.
+    checksum: Option<String>,
+    /// Give the plugin a free `<name>-N` name rather than replace an installed one.
+    rename_on_collision: bool,
+    /// Symlink to `path` instead of copying it; see `Manifest::linked`.
+    link: bool,
+    /// Merged into the manifest's `tags`.
+    tags: Vec<String>,
+    /// Replaces the manifest's `description` in the sidecar.
+    description: Option<String>,
+    /// Fall back to `bare_manifest` when `--manifest` fails.
+    allow_manifest_failure: bool,
+    /// Name for the bare manifest; skips `--manifest` entirely.
+    name: Option<String>,
+    /// Register a binary under a bare manifest; see `Manifest::executable`.
+    executable: bool,
+    /// Warn about missing `requires_plugins` instead of refusing.
+    force: bool,
+    /// Count as installed for that check: the rest of a `sync` batch.
+    provided: Vec<String>,
+    /// `sh -c` commands around the install; see `run_hook`.
+    pre_hook: Option<String>,
+    post_hook: Option<String>,
+    /// Report a failing hook instead of failing the install.
+    hook_warn_only: bool,
+    /// Merged into the manifest's `commands` before it is checked.
+    subcommands: Vec<SubCmdMeta>,
+    /// Passed to `uv run` for `--manifest`; appended to the manifest's `uv_args`.
+    uv_args: Vec<String>,
+    /// Archive a replaced plugin under `backups_dir`; never pruned.
+    overwrite_backup: bool,
+    /// Launch the installed plugin once; see `smoke_test`.
+    smoke_test: bool,
+    /// Merged over the `extra` a reinstalled plugin already had.
+    extra: Vec<(String, String)>,
+    /// Don't require `Manifest::interpreter` (or the shebang's) to be installed.
+    skip_interpreter_check: bool,
+}
+
+const DEFAULT_MANIFEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn validate_and_copy(path: &Path, opts: &InstallOpts) -> Result<Manifest, Box<dyn std::error::Error>> {
+    if let Some(want) = &opts.checksum {
+        verify_checksum(path, want)?;                 // before `--manifest` executes it
+    }
+    let stem = || path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+    let mut manifest = match (&opts.name, opts.allow_manifest_failure) {
+        _ if opts.executable => Manifest {
+            description: format!("{} (executable)", path.file_name().unwrap_or_default().to_string_lossy()),
+            ..bare_manifest(path, opts.name.clone().unwrap_or_else(stem).as_str(), true)
+        },
+        (Some(name), true) => bare_manifest(path, name, opts.no_exec),
+        (None, true) => resolve_manifest(path, opts).or_else(|e| {
+            diag!("ℹ️  no usable manifest ({e}); registering as a passthrough plugin");
+            Ok::<_, Box<dyn std::error::Error>>(bare_manifest(path, &stem(), opts.no_exec))
+        })?,
+        _ => resolve_manifest(path, opts)?,
+    };
+    for sc in &opts.subcommands {
+        match manifest.commands.iter_mut().find(|c| c.name == sc.name) {
+            Some(c) => c.description = sc.description.clone(),
+            None => manifest.commands.push(sc.clone()),
+        }
+    }
+    check_manifest(&manifest)?;
+    if opts.rename_on_collision
+        && let Some(old) = rename_if_taken(&mut manifest)
+    {
+        let verb = if opts.validate_only { "would install" } else { "installing" };
+        diag!("ℹ️  `{old}` is already installed; {verb} as `{}`", manifest.name);
+    }
+    warn_alias_collisions(&manifest);
+    check_dependencies(&manifest, opts)?;
+    if !opts.no_exec && !opts.skip_interpreter_check {
+        check_interpreter(&manifest, path)?;
+    }
+    if opts.validate_only {
+        return Ok(manifest);
+    }
+    if let Some(hook) = &opts.pre_hook {
+        run_hook("--pre", hook, &manifest, opts.hook_warn_only)?;
+    }
+    let manifest = install_plugin(path, manifest, opts)?;
+    if opts.smoke_test {
+        smoke_test(&manifest)?;
+    }
+    if let Some(hook) = &opts.post_hook {
+        run_hook("--post", hook, &manifest, opts.hook_warn_only)?;
+    }
+    Ok(manifest)
+}
+
+/// Run an `add --pre/--post` command through `sh -c` with UNI_PLUGIN_NAME,
+/// UNI_PLUGIN_VERSION and UNI_PLUGIN_PATH set.  Its output goes straight to
+/// the terminal; a non-zero exit fails the add unless `warn_only`.
+fn run_hook(which: &str, hook: &str, m: &Manifest, warn_only: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if verbose() {
+        diag!("🪝  {which}: {hook}");
+    }
+    let status = Cmd::new("sh")
+        .arg("-c")
+        .arg(hook)
+        .env("UNI_PLUGIN_NAME", &m.name)
+        .env("UNI_PLUGIN_VERSION", &m.version)
+        .env("UNI_PLUGIN_PATH", plugin_dir().join(&m.name))
+        .status()?;
+    if status.success() {
+        return Ok(());
+    }
+    let what = match status.code() {
+        Some(code) => format!("exited with code {code}"),
+        None => "was killed by a signal".into(),
+    };
+    if warn_only {
+        diag!("⚠️  {which} hook {what}");
+        return Ok(());
+    }
+    let after = if which == "--post" { format!("; `{}` is installed", m.name) } else { String::new() };
+    Err(format!("{which} hook {what}{after}").into())
+}
+
+/// Refuse `m` while any of its `requires_plugins` is not installed; with
+/// `opts.force`, only say which.
+fn check_dependencies(m: &Manifest, opts: &InstallOpts) -> Result<(), Box<dyn std::error::Error>> {
+    let mut installed: std::collections::HashSet<String> = load_manifests().into_iter().map(|m| m.name).collect();
+    installed.extend(opts.provided.iter().cloned());
+    let missing: Vec<&str> = m.requires_plugins.iter().filter(|d| !installed.contains(*d)).map(String::as_str).collect();
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let list = missing.iter().map(|d| format!("`{d}`")).collect::<Vec<_>>().join(", ");
+    if opts.force {
+        diag!("⚠️  `{}` requires {list}, not installed; installing anyway", m.name);
+        return Ok(());
+    }
+    Err(format!("`{}` requires {list}, not installed (add them first, or pass --force)", m.name).into())
+}
+
+fn name_taken(name: &str) -> bool {
+    let dir = plugin_dir();
+    dir.join(name).exists() || dir.join(format!("{name}.json")).exists() || asset_dir(name).exists()
+}
+
+/// Point `m` at the first free `<name>-N` (N ≥ 2) if `<name>` is taken;
+/// returns the original name when it did.
+fn rename_if_taken(m: &mut Manifest) -> Option<String> {
+    if !name_taken(&m.name) {
+        return None;
+    }
+    let free = (2..).map(|n| format!("{}-{n}", m.name)).find(|n| !name_taken(n))?;
+    Some(std::mem::replace(&mut m.name, free))
+}
+
+const SMOKE_TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `add --smoke-test`: launch the installed plugin as dispatch would, with
+/// its first subcommand that takes no required arguments (else `--help`).
+/// Exit 2 is a usage error and still running at the timeout is a server;
+/// either way the plugin started, which is all this checks.
+fn smoke_test(m: &Manifest) -> Result<(), Box<dyn std::error::Error>> {
+    let arg = m.commands
+        .iter()
+        .find(|c| !c.args.iter().flatten().any(|a| a.required))
+        .map_or("--help", |c| c.name.as_str());
+    let what = format!("`uni {} {arg}`", m.name);
+    let undo = format!("it stays installed (`uni remove {}` to undo)", m.name);
+    match output_with_timeout(plugin_command(Some(m), &plugin_dir().join(&m.name)).arg(arg), SMOKE_TEST_TIMEOUT) {
+        Err(e) if e.kind() == std::io::ErrorKind::TimedOut => {
+            say(format_args!("🔥 smoke test: {what} still running after {}s; counting it as started", SMOKE_TEST_TIMEOUT.as_secs()));
+            Ok(())
+        }
+        Err(e) => Err(format!("smoke test: {what} could not start: {e}; {undo}").into()),
+        Ok(out) if out.status.success() || out.status.code() == Some(2) => {
+            say(format_args!("🔥 smoke test: {what} ran"));
+            Ok(())
+        }
+        Ok(out) => {
+            let stderr = String::from_utf8_lossy(&out.stderr);
+            let last = stderr.trim().lines().last().unwrap_or("no output");
+            Err(format!("smoke test: {what} failed ({}): {last}; {undo}", out.status).into())
+        }
+    }
+}
+
+/// What `uni add --dry-run` would do with an already validated `manifest`:
+/// the files it would write, what they replace, and the sidecar itself.
+fn print_install_plan(path: &Path, mut manifest: Manifest, opts: &InstallOpts) -> Result<(), Box<dyn std::error::Error>> {
+    apply_install_opts(&mut manifest, opts);
+    let dest = plugin_dir().join(&manifest.name);
+    if porcelain() {
+        println!("{}", dest.display());
+        return Ok(());
+    }
+    println!("Would install `{}` v{}", manifest.name, manifest.version);
+    if opts.link {
+        println!("  script:   {} → {}", dest.display(), fs::canonicalize(path).ctx("resolving", path)?.display());
+    } else {
+        println!("  script:   {} ({})", dest.display(), if manifest.no_exec { "0644" } else { "0755" });
+    }
+    println!("  manifest: {}", plugin_dir().join(format!("{}.json", manifest.name)).display());
+    match read_sidecar(&manifest.name) {
+        Some(old) => {
+            let kept = if opts.overwrite_backup { format!(", archived under {}", backups_dir(&old.name).display()) } else { String::new() };
+            println!("  replaces: v{} (kept in `uni rollback` history{kept})", old.version);
+        }
+        None => println!("  replaces: nothing"),
+    }
+    println!("{}", serde_json::to_string_pretty(&manifest)?);
+    Ok(())
+}
+
+/// The program a script's shebang starts: `#!/usr/bin/env -S uv run` gives
+/// `uv`, `#!/bin/bash -e` gives `/bin/bash`.
+fn shebang_program(script: &Path) -> Option<String> {
+    let text = fs::read(script).ok()?;
+    let line = text.split(|&b| b == b'\n').next()?.strip_prefix(b"#!")?;
+    let mut words = std::str::from_utf8(line).ok()?.split_whitespace();
+    let first = words.next()?;
+    if !first.ends_with("/env") {
+        return Some(first.to_owned());
+    }
+    // env's own options and VAR=value assignments come before the program
+    words.find(|w| !w.starts_with('-') && !w.contains('=')).map(str::to_owned)
+}
+
+/// `program` as the kernel or `env` would find it: a path as-is, a bare name
+/// on PATH.  Only executable files count.
+fn find_program(program: &str) -> Option<PathBuf> {
+    let runnable = |p: &Path| fs::metadata(p).is_ok_and(|md| md.is_file() && md.permissions().mode() & 0o111 != 0);
+    if program.contains('/') {
+        return Some(PathBuf::from(program)).filter(|p| runnable(p));
+    }
+    std::env::split_paths(&std::env::var_os("PATH")?).map(|d| d.join(program)).find(|p| runnable(p))
+}
+
+/// Refuse a plugin whose interpreter isn't installed: it could never run.
+/// A script with neither `interpreter` nor a shebang has nothing to check.
+fn check_interpreter(m: &Manifest, script: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(program) = m.interpreter.clone().or_else(|| shebang_program(script)) else { return Ok(()) };
+    if find_program(&program).is_none() {
+        let where_ = if program.contains('/') { "no such executable" } else { "not on PATH" };
+        return Err(format!(
+            "`{}` needs `{program}`, which isn't installed here ({where_}); install it or pass --no-interpreter-check",
+            m.name
+        ).into());
+    }
+    Ok(())
+}
+
+/// Stand-in manifest for a script that has none: no subcommands, so every
+/// argument is passed straight through.
+fn bare_manifest(path: &Path, name: &str, no_exec: bool) -> Manifest {
+    if !no_exec && !fs::read(path).is_ok_and(|b| b.starts_with(b"#!")) {
+        diag!("⚠️  {} has no shebang; add `--no-exec` to launch it through `uv run`", path.display());
+    }
+    Manifest {
+        name: name.to_owned(),
+        description: format!("{} (no manifest)", path.file_name().unwrap_or_default().to_string_lossy()),
+        version: "0.0.0".into(),
+        manifest_origin: ManifestOrigin::None,
+        ..Default::default()
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+fn verify_checksum(path: &Path, want: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let got = sha256_hex(&fs::read(path).ctx("reading", path)?);
+    if !got.eq_ignore_ascii_case(want.trim()) {
+        return Err(format!("checksum mismatch for {}:\n  expected {want}\n  got      {got}", path.display()).into());
+    }
+    Ok(())
+}
+
+/// Everything wrong with a manifest that would make it uninstallable or
+/// unreachable once installed.
+fn manifest_problems(m: &Manifest) -> Vec<String> {
+    let mut problems = Vec::new();
+    let name = m.name.as_str();
+
+    if name.is_empty() {
+        problems.push("`name` is empty".into());
+    } else if Path::new(name).file_name() != Some(std::ffi::OsStr::new(name)) || name.contains('\\') {
+        problems.push(format!("`name` {name:?} must be a plain file name"));
+    } else if name.starts_with('.') {
+        // `.history` and friends are uni's; a dotted plugin also hides from `list`/`prune`
+        problems.push(format!("`name` {name:?} must not start with `.`"));
+    } else if name.ends_with(".json") || name.ends_with(".d") || name == "backups" {
+        problems.push(format!("`name` {name:?} clashes with uni's own files in the plugin dir"));
+    }
+    // a plugin named like a built-in would never be dispatched
+    if Cli::command().get_subcommands().any(|c| c.get_name() == name) {
+        problems.push(format!("`name` {name:?} collides with the built-in `uni {name}`"));
+    }
+    if m.version.trim().is_empty() {
+        problems.push("`version` is empty".into());
+    }
+    for a in &m.aliases {
+        if a.is_empty() || a.starts_with('-') || a.contains(char::is_whitespace) || a.contains('/') {
+            problems.push(format!("alias {a:?} is not a plain word"));
+        } else if a == name || builtin_names().any(|b| b == *a) {
+            problems.push(format!("alias `{a}` collides with {}", if a == name { "the plugin's own name" } else { "a built-in" }));
+        }
+    }
+    if let Some(p) = uni_version_problem(m) {
+        problems.push(p);
+    }
+    if m.requires_plugins.contains(&m.name) {
+        problems.push(format!("`{name}` lists itself in `requires_plugins`"));
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    for c in &m.commands {
+        if c.name.is_empty() {
+            problems.push("a subcommand has an empty `name`".into());
+        } else if c.name == "help" {
+            problems.push("subcommand `help` is reserved".into());
+        } else if !seen.insert(c.name.as_str()) {
+            problems.push(format!("subcommand `{}` is declared twice", c.name));
+        }
+        problems.extend(arg_spec_problems(c));
+    }
+    problems
+}
+
+/// Set when the plugin's `min_uni_version` is newer than this binary.
+fn uni_version_problem(m: &Manifest) -> Option<String> {
+    let min = m.min_uni_version.as_deref()?;
+    let have = env!("CARGO_PKG_VERSION");
+    match cmp_versions(have, min) {
+        Some(std::cmp::Ordering::Less) => Some(format!(
+            "`{}` needs uni {min} or newer (this is {have}); upgrade uni to use it", m.name
+        )),
+        Some(_) => None,
+        None => Some(format!("`min_uni_version` {min:?} is not a version")),
+    }
+}
+
+// clap rejects (panics on) these when building the subcommand, so catch them at add time
+fn arg_spec_problems(c: &SubCmdMeta) -> Vec<String> {
+    let mut problems = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    let mut optional_positional = false;
+    for a in c.args.iter().flatten() {
+        let ok_name = !a.name.is_empty()
+            && !a.name.starts_with('-')
+            && a.name.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+        if !ok_name {
+            problems.push(format!("`{}`: bad argument name {:?}", c.name, a.name));
+        } else if a.name == "help" {
+            problems.push(format!("`{}`: argument `help` is reserved", c.name));
+        } else if Cli::command().get_arguments().any(|g| g.get_id() == a.name.as_str() || g.get_long() == Some(a.name.as_str())) {
+            problems.push(format!("`{}`: argument `{}` clashes with uni's global --{}", c.name, a.name, a.name));
+        } else if !seen.insert(a.name.as_str()) {
+            problems.push(format!("`{}`: argument `{}` is declared twice", c.name, a.name));
+        }
+        if a.is_positional() {
+            if a.required && optional_positional {
+                problems.push(format!("`{}`: required argument `{}` follows an optional one", c.name, a.name));
+            }
+            optional_positional |= !a.required;
+        }
+    }
+    problems
+}
+
+// built-in subcommand names and aliases, plus clap's `help`
+fn builtin_names() -> impl Iterator<Item = String> {
+    let cli = Cli::command();
+    let mut names: Vec<String> = cli
+        .get_subcommands()
+        .flat_map(|c| std::iter::once(c.get_name()).chain(c.get_all_aliases()))
+        .map(str::to_owned)
+        .collect();
+    names.push("help".into());
+    names.into_iter()
+}
+
+/// A word two things want in the top-level CLI.  `word == plugin` means the
+/// plugin's own name is taken (same name in two sidecars); otherwise it's one
+/// of its aliases.
+struct Collision {
+    plugin: String,
+    word: String,
+    taken_by: String,
+}
+
+/// Plugin names first, then aliases, each in name order: the first claimant
+/// keeps a word, everyone later is reported (and left out of `build_cli`,
+/// where clap would otherwise refuse the duplicate).
+fn cli_collisions(plugins: &[Manifest]) -> Vec<Collision> {
+    let mut taken: std::collections::HashMap<String, String> =
+        builtin_names().map(|n| (n.clone(), format!("the built-in `uni {n}`"))).collect();
+    let mut sorted: Vec<&Manifest> = plugins.iter().collect();
+    sorted.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut out = Vec::new();
+    let mut claim = |plugin: &str, word: &str, owner: String| match taken.get(word) {
+        Some(by) => out.push(Collision { plugin: plugin.into(), word: word.into(), taken_by: by.clone() }),
+        None => { taken.insert(word.into(), owner); }
+    };
+    for m in &sorted {
+        claim(&m.name, &m.name, format!("plugin `{}`", m.name));
+    }
+    for m in &sorted {
+        for a in &m.aliases {
+            claim(&m.name, a, format!("an alias of `{}`", m.name));
+        }
+    }
+    out
+}
+
+// installing still works; the colliding alias just won't be registered
+fn warn_alias_collisions(new: &Manifest) {
+    let mut all: Vec<Manifest> = load_manifests().into_iter().filter(|m| m.name != new.name).collect();
+    all.push(Manifest { name: new.name.clone(), aliases: new.aliases.clone(), ..Default::default() });
+    for c in cli_collisions(&all) {
+        if c.plugin == new.name || c.taken_by.contains(&format!("`{}`", new.name)) {
+            diag!("⚠️  `{}` of plugin `{}` is already {}; that alias will be ignored", c.word, c.plugin, c.taken_by);
+        }
+    }
+}
+
+fn check_manifest(m: &Manifest) -> Result<(), Box<dyn std::error::Error>> {
+    let problems = manifest_problems(m);
+    if problems.is_empty() {
+        return Ok(());
+    }
+    Err(format!("invalid manifest:\n  - {}", problems.join("\n  - ")).into())
+}
+
+/// The manifest for a script: its companion sidecar, else `--manifest`.
+fn resolve_manifest(path: &Path, opts: &InstallOpts) -> Result<Manifest, Box<dyn std::error::Error>> {
+    match companion_sidecar(path)? {
+        Some(m) => Ok(Manifest { manifest_origin: ManifestOrigin::Sidecar, ..m }),
+        None => fetch_manifest(path, opts),
+    }
+}
+
+/// Fill in the sidecar fields that come from `opts` rather than the plugin.
+fn apply_install_opts(manifest: &mut Manifest, opts: &InstallOpts) {
+    manifest.source = opts.source.clone();
+    let old = read_sidecar(&manifest.name);
+    manifest.pinned = opts.pin || old.as_ref().is_some_and(|m| m.pinned);
+    // `--tag`s from an earlier install stay, like `--set` values
+    let old_tags = old.as_ref().map(|m| m.tags.clone()).unwrap_or_default();
+    let old_description = old.as_ref().filter(|m| m.custom_description).map(|m| m.description.clone());
+    manifest.extra = old.map(|m| m.extra).unwrap_or_default();
+    manifest.extra.extend(opts.extra.iter().cloned());
+
+    manifest.no_exec = opts.no_exec;
+    manifest.executable = opts.executable;
+    manifest.linked = opts.link;
+    manifest.sha256 = None;
+    if let Some(d) = opts.description.clone().or(old_description) {
+        manifest.description = d;
+        manifest.custom_description = true;
+    }
+    for t in old_tags.iter().chain(&opts.tags) {
+        if !manifest.tags.contains(t) {
+            manifest.tags.push(t.clone());
+        }
+    }
+    manifest.uv_args.extend(opts.uv_args.iter().cloned());
+}
+
+/// Copy a script whose manifest is already resolved into `plugin_dir()`.
+fn install_plugin(path: &Path, mut manifest: Manifest, opts: &InstallOpts) -> Result<Manifest, Box<dyn std::error::Error>> {
+    apply_install_opts(&mut manifest, opts);
+
+    if opts.overwrite_backup
+        && let Some((entry, old)) = archive_plugin(&manifest.name)?
+    {
+        say(format_args!("🗄️  Kept the replaced v{} in {}", old.version, entry.display()));
+    }
+    if opts.link {
+        return link_plugin(path, manifest);
+    }
+
+    // Copy script: prepare it in a staging dir, then move it into place so a
+    // failed install never leaves a half-written plugin behind
+    let staging = tempfile::tempdir()?;
+    let staged = staging.path().join(&manifest.name);
+    fs::copy(path, &staged).ctx("copying", path)?;
+    if !manifest.no_exec && !manifest.executable {
+        fix_shebang(&staged)?;                        // the shebang is never used otherwise
+    }
+    let mut perm = fs::metadata(&staged).ctx("reading", &staged)?.permissions();
+    perm.set_mode(if manifest.no_exec { 0o644 } else { 0o755 });
+    fs::set_permissions(&staged, perm).ctx("setting permissions on", &staged)?;
+    manifest.sha256 = Some(sha256_hex(&fs::read(&staged).ctx("reading", &staged)?));  // after fix_shebang: what's on disk
+    backup_plugin(&manifest.name)?;
+    place_file(&staged, &plugin_dir().join(&manifest.name))?;
+
+    // Save manifest JSON
+    write_sidecar(&manifest)?;
+
+    Ok(manifest)
+}
+
+/// `add --link`: point `plugin_dir()/<name>` at the source file itself.  The
+/// source's shebang and mode are used as they are, so only warn about them.
+fn link_plugin(path: &Path, manifest: Manifest) -> Result<Manifest, Box<dyn std::error::Error>> {
+    let target = fs::canonicalize(path).ctx("resolving", path)?;
+    if fs::metadata(&target).ctx("reading", &target)?.permissions().mode() & 0o111 == 0 {
+        diag!("⚠️  {} is not executable; `chmod +x` it or the plugin won't start", target.display());
+    }
+    // staged next to the destination: place_file's cross-device fallback
+    // would copy the file content instead of the link
+    let dest = plugin_dir().join(&manifest.name);
+    let staged = dest.with_file_name(format!("{}.uni-tmp", manifest.name));
+    let _ = fs::remove_file(&staged);
+    std::os::unix::fs::symlink(&target, &staged).ctx("creating symlink", &staged)?;
+    backup_plugin(&manifest.name)?;
+    fs::rename(&staged, &dest).ctx("moving into place", &dest)?;
+    write_sidecar(&manifest)?;
+    Ok(manifest)
+}
+
+/// Move `staged` to `dest`.  A plain rename is atomic, but fails with EXDEV when
+/// the staging dir and `plugin_dir()` (e.g. via `UNI_PLUGIN_DIR`) sit on
+/// different filesystems; then copy next to `dest` and rename from there.
+fn place_file(staged: &Path, dest: &Path) -> Result<(), IoError> {
+    let rename_err = match fs::rename(staged, dest) {
+        Ok(()) => return Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => e,
+        Err(e) => return Err(e).ctx("moving into place", dest),
+    };
+
+    let mut near = dest.as_os_str().to_owned();
+    near.push(".uni-tmp");
+    let near = PathBuf::from(near);
+    let fallback = fs::copy(staged, &near)
+        .and_then(|_| fs::rename(&near, dest))
+        .and_then(|_| fs::remove_file(staged));
+    fallback.map_err(|copy_err| {
+        let _ = fs::remove_file(&near);
+        IoError::other(format!(
+            "could not install {}: rename failed ({rename_err}), copy fallback failed ({copy_err})",
+            dest.display()
+        ))
+    })
+}
+
+fn fmt_duration(d: Duration) -> String {
+    format!("{:.2}s", d.as_secs_f64())
+}
+
+/// A `foo.json` sitting next to `foo.py` (see `uni create --static`) stands in
+/// for the `--manifest` call.  Only scripts with an extension qualify: installed
+/// and exported plugins are extension-less, so archives still get validated.
+fn companion_sidecar(path: &Path) -> Result<Option<Manifest>, Box<dyn std::error::Error>> {
+    if path.extension().is_none_or(|e| e == "json") {
+        return Ok(None);
+    }
+    let json = path.with_extension("json");
+    if !json.is_file() {
+        return Ok(None);
+    }
+    let m = parse_plugin_manifest(&fs::read(&json).ctx("reading", &json)?)
+        .map_err(|e| format!("{}: {e}", json.display()))?;
+    Ok(Some(m))
+}
+
+/// Run `<script> --manifest` and parse what it prints.
+fn fetch_manifest(path: &Path, opts: &InstallOpts) -> Result<Manifest, Box<dyn std::error::Error>> {
+    let mut cmd = uv_run(&opts.uv_args, path);  // interpreter call avoids chmod issues
+    cmd.arg("--manifest");
+    let timeout = opts.manifest_timeout.unwrap_or(DEFAULT_MANIFEST_TIMEOUT);
+    let started = Instant::now();
+    let out = output_with_timeout(&mut cmd, timeout).map_err(|e| {
+        if e.kind() == std::io::ErrorKind::TimedOut {
+            format!("{}: `--manifest` did not finish within {}s", path.display(), timeout.as_secs()).into()
+        } else if e.kind() == std::io::ErrorKind::NotFound {
+            format!("cannot run `{}` (set UNI_UV_BIN or `uv_bin` in the config)", resolve_uv_bin().value.display()).into()
+        } else {
+            Box::<dyn std::error::Error>::from(e)
+        }
+    })?;
+    if verbose() {
+        diag!("⏱  {}: `uv run --manifest` took {}", path.display(), fmt_duration(started.elapsed()));
+    }
+    if !out.status.success() {
+        diag!(
+            "plugin did not return valid manifest (exit {}):\n{}",
+            out.status,
+            String::from_utf8_lossy(&out.stderr)
+        );
+    }
+    parse_plugin_manifest(&out.stdout).map_err(|e| format!("`--manifest` output: {e}").into())
+}
+
+/// Parse manifest JSON, tolerating a UTF-8 BOM and surrounding whitespace
+/// (Windows editors, `print()` with extra newlines).  JSON that parses but
+/// carries no `name`/`version` (an error object, say) is rejected as "not a
+/// manifest" rather than installed half-empty.
+fn parse_manifest(bytes: &[u8]) -> Result<Manifest, String> {
+    parse_manifest_without(bytes, &[])
+}
+
+/// `parse_manifest` for what a plugin declares (`--manifest` output, a
+/// companion `.json`): the keys uni writes itself are dropped, so a plugin
+/// can't claim a `sha256` or `"manifest_origin": "none"`.
+fn parse_plugin_manifest(bytes: &[u8]) -> Result<Manifest, String> {
+    parse_manifest_without(bytes, SIDECAR_ONLY_KEYS)
+}
+
+fn parse_manifest_without(bytes: &[u8], dropped: &[&str]) -> Result<Manifest, String> {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let mut v: serde_json::Value = serde_json::from_slice(bytes.trim_ascii()).map_err(|e| format!("not valid JSON: {e}"))?;
+    let Some(obj) = v.as_object_mut() else {
+        return Err(format!("expected a manifest object, got {}", json_kind(&v)));
+    };
+    for key in dropped {
+        obj.remove(*key);
+    }
+    let usable = |k: &str| obj.get(k).and_then(|x| x.as_str()).is_some_and(|s| !s.trim().is_empty());
+    if !usable("name") || !usable("version") {
+        let keys: Vec<&str> = obj.keys().map(String::as_str).collect();
+        return Err(format!(
+            "this JSON is not a manifest: it needs non-empty string `name` and `version` \
+            (top-level keys: {}); see `uni schema`",
+            if keys.is_empty() { "none".into() } else { keys.join(", ") }
+        ));
+    }
+    serde_json::from_value(v).map_err(|e| e.to_string())
+}
+
+fn json_kind(v: &serde_json::Value) -> &'static str {
+    match v {
+        serde_json::Value::Null      => "null",
+        serde_json::Value::Bool(_)   => "a boolean",
+        serde_json::Value::Number(_) => "a number",
+        serde_json::Value::String(_) => "a string",
+        serde_json::Value::Array(_)  => "an array",
+        serde_json::Value::Object(_) => "an object",
+    }
+}
+
+/// How to launch an installed plugin: exec the script directly (its shebang
+/// picks the interpreter) or, for `no_exec` plugins, through `uv run`.
+fn plugin_command(m: Option<&Manifest>, script: &Path) -> Cmd {
+    let mut cmd = match m {
+        Some(m) if m.no_exec => uv_run(&m.uv_args, script),
+        _ => Cmd::new(script),
+    };
+    if let Some(m) = m {
+        cmd.envs(&m.env);
+        // trampolines and `uv run` inside the plugin find the same uv we do
+        let uv = resolve_uv_bin();
+        if !matches!(uv.origin, Origin::Default) {
+            cmd.env("UNI_UV_BIN", uv.value);
+        }
+        // a profile the plugin doesn't know is fine: the flag is global
+        if let Some(vars) = global_flags().profile.as_ref().and_then(|p| m.env_overrides.get(p)) {
+            cmd.envs(vars);
+        }
+    }
+    cmd
+}
+
+/* ---------- install sources ---------- */
+
+// git sources are recorded as `git+<url>#ref=<ref>&path=<path>` (ref optional)
+fn pypi_source(requirement: &str, entry_point: Option<&str>) -> String {
+    match entry_point {
+        Some(e) => format!("pypi:{requirement}#entry={e}"),
+        None    => format!("pypi:{requirement}"),
+    }
+}
+
+fn git_source(url: &str, git_ref: Option<&str>, script: &str) -> String {
+    match git_ref {
+        Some(r) => format!("git+{url}#ref={r}&path={script}"),
+        None    => format!("git+{url}#path={script}"),
+    }
+}
+
+/// Install from a recorded `source` string: a local path, a URL, a `git+`
+/// URL or a `pypi:` requirement.
+fn install_from_source(src: &str, opts: &InstallOpts) -> Result<Manifest, Box<dyn std::error::Error>> {
+    let opts = InstallOpts { source: Some(src.to_owned()), ..opts.clone() };
+    if is_url(src) {
+        let tmp = tempfile::tempdir()?;
+        let file = download(src, tmp.path())?;
+        return validate_and_copy(&file, &opts);
+    }
+    if let Some(rest) = src.strip_prefix("pypi:") {
+        let (requirement, entry) = match rest.split_once("#entry=") {
+            Some((r, e)) => (r, Some(e)),
+            None => (rest, None),
+        };
+        let tmp = tempfile::tempdir()?;
+        let file = fetch_pypi(requirement, entry, tmp.path())?;
+        return validate_and_copy(&file, &opts);
+    }
+    let Some(rest) = src.strip_prefix("git+") else {
+        return validate_and_copy(Path::new(src), &opts);
+    };
+
+    let (url, frag) = rest.split_once('#').ok_or_else(|| format!("git source `{src}` has no #path="))?;
+    let mut git_ref = None;
+    let mut script = None;
+    for kv in frag.split('&') {
+        match kv.split_once('=') {
+            Some(("ref", v))  => git_ref = Some(v),
+            Some(("path", v)) => script = Some(v),
+            _ => return Err(format!("bad git source fragment `{kv}`").into()),
+        }
+    }
+    let script = script.ok_or_else(|| format!("git source `{src}` has no path="))?;
+
+    let tmp = tempfile::tempdir()?;
+    fetch_git(url, git_ref, tmp.path())?;
+    let file = tmp.path().join(script);
+    if !file.is_file() {
+        return Err(format!("`{script}` not found in {url}").into());
+    }
+    validate_and_copy(&file, &opts)
+}
+
+fn is_url(src: &str) -> bool {
+    src.starts_with("https://") || src.starts_with("http://")
+}
+
+/// Fetch `url` into `dir`, keeping its last path segment as the file name so
+/// `foo.py` + companion-sidecar logic still sees an extension.
+fn download(url: &str, dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    require_online(&format!("downloading {url}"))?;
+    let name = url
+        .split(['?', '#']).next().unwrap_or(url)
+        .rsplit('/').next().filter(|n| !n.is_empty())
+        .unwrap_or("plugin");
+    let dest = dir.join(name);
+    // curl is already what the uv/pyenv installers rely on
+    let out = Cmd::new("curl").args(["-fsSL", "-o"]).arg(&dest).arg(url).output()?;
+    if !out.status.success() {
+        return Err(format!("download of {url} failed: {}", String::from_utf8_lossy(&out.stderr).trim()).into());
+    }
+    Ok(dest)
+}
+
+/// Shallow-fetch a single ref (branch, tag or commit; default HEAD) into `dir`.
+fn fetch_git(url: &str, git_ref: Option<&str>, dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    require_online("fetching a git source")?;
+    let git = |args: &[&str]| -> Result<(), Box<dyn std::error::Error>> {
+        let out = Cmd::new("git").arg("-C").arg(dir).args(args).output()?;
+        if !out.status.success() {
+            return Err(format!(
+                "git {} failed:\n{}", args[0], String::from_utf8_lossy(&out.stderr).trim()
+            ).into());
+        }
+        Ok(())
+    };
+    git(&["init", "--quiet"])?;
+    git(&["remote", "add", "origin", url])?;
+    // fetch-by-name works for commits too, which `clone --branch` can't do
+    git(&["fetch", "--quiet", "--depth", "1", "origin", git_ref.unwrap_or("HEAD")])?;
+    git(&["checkout", "--quiet", "FETCH_HEAD"])?;
+    Ok(())
+}
+
+// lists a distribution's console scripts as `[[name, "module:attr"], …]`
+const PYPI_PROBE: &str = r#"import json, sys
+from importlib.metadata import distribution
+d = distribution(sys.argv[1])
+print(json.dumps([[e.name, e.value] for e in d.entry_points if e.group == "console_scripts"]))
+"#;
+
+/// Resolve `requirement` with uv, pick its console script, and write a PEP 723
+/// shim into `dir` that depends on the package and calls that entry point.
+/// The shim is what gets installed; uv fetches the package when it runs.
+fn fetch_pypi(requirement: &str, entry: Option<&str>, dir: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    require_online(&format!("resolving `{requirement}` from PyPI"))?;
+    let dist = requirement
+        .split(|c: char| "=<>!~[;@ ".contains(c))
+        .next()
+        .filter(|d| !d.is_empty())
+        .ok_or_else(|| format!("`{requirement}` is not a package requirement"))?;
+
+    let out = uv()
+        .args(["run", "--no-project", "--with", requirement, "python", "-c", PYPI_PROBE, dist])
+        .stdin(std::process::Stdio::null())
+        .output()?;
+    if !out.status.success() {
+        return Err(format!("uv could not resolve `{requirement}`:\n{}", String::from_utf8_lossy(&out.stderr).trim()).into());
+    }
+    let scripts: Vec<(String, String)> = serde_json::from_slice(&out.stdout)
+        .map_err(|e| format!("unexpected output while inspecting `{dist}`: {e}"))?;
+    let names = || scripts.iter().map(|(n, _)| n.as_str()).collect::<Vec<_>>().join(", ");
+    let chosen = match entry {
+        Some(e) => scripts.iter().find(|(n, _)| n == e)
+            .ok_or_else(|| format!("`{dist}` has no console script `{e}` (it has: {})", names()))?,
+        None => match scripts.as_slice() {
+            [] => return Err(format!("`{dist}` declares no console scripts").into()),
+            [only] => only,
+            _ => scripts.iter().find(|(n, _)| n == dist)
+                .ok_or_else(|| format!("`{dist}` has several console scripts ({}); pick one with --entry-point", names()))?,
+        },
+    };
+
+    let name = &chosen.0;
+    let shim = format!(
+        "#!/usr/bin/env -S uv run --script\n\
+         # /// script\n\
+         # requires-python = \">=3.10\"\n\
+         # dependencies = [\"{req}\"]\n\
+         # ///\n\
+         # installed by `uni add --from-pypi`: runs the `{name}` entry point ({value})\n\
+         import sys\n\
+         from importlib.metadata import entry_points\n\
+         \n\
+         sys.path.pop(0)  # this file's dir: a shim named like the package would shadow it\n\
+         \n\
+         if __name__ == \"__main__\":\n    \
+             (ep,) = entry_points(group=\"console_scripts\", name=\"{py_name}\")\n    \
+             sys.argv[0] = \"{py_name}\"\n    \
+             sys.exit(ep.load()())\n",
+        req = py_str_escape(requirement),
+        value = chosen.1,
+        py_name = py_str_escape(name),
+    );
+    let path = dir.join(format!("{}.py", file_safe(name)));
+    fs::write(&path, shim).ctx("writing", &path)?;
+    Ok(path)
+}
+
+fn read_sidecar(name: &str) -> Option<Manifest> {
+    let bytes = fs::read(plugin_dir().join(format!("{name}.json"))).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn write_sidecar(m: &Manifest) -> Result<(), Box<dyn std::error::Error>> {
+    let staging = tempfile::tempdir()?;
+    let staged = staging.path().join("sidecar.json");
+    fs::write(&staged, serde_json::to_vec_pretty(m)?).ctx("writing", &staged)?;
+    // 0o644 whatever the umask, like the sidecars `export` archives
+    fs::set_permissions(&staged, fs::Permissions::from_mode(0o644)).ctx("setting permissions on", &staged)?;
+    place_file(&staged, &plugin_dir().join(format!("{}.json", m.name)))?;
+    Ok(())
+}
+
+fn set_pinned(name: &str, pinned: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut m = read_sidecar(name).ok_or_else(|| format!("no plugin `{name}`"))?;
+    m.pinned = pinned;
+    write_sidecar(&m)
+}
+
+/// `uni set` / `uni unset`: edit `extra` in place.  Unsetting a key that
+/// isn't there is fine; the end state is what was asked for.
+fn set_extra(name: &str, set: &[(String, String)], unset: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut m = read_sidecar(name).ok_or_else(|| format!("no plugin `{name}`"))?;
+    m.extra.extend(set.iter().cloned());
+    for k in unset {
+        m.extra.remove(k);
+    }
+    write_sidecar(&m)
+}
+
+/// Like `Command::output`, but kills the child once `timeout` elapses.  stdin
+/// is closed so a plugin blocked on input sees EOF rather than hanging.
+fn output_with_timeout(cmd: &mut Cmd, timeout: Duration) -> std::io::Result<std::process::Output> {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // drain both pipes on threads so a chatty child can't fill one and stall
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut p) = pipe { let _ = p.read_to_end(&mut buf); }
+            buf
+        })
+    };
+    let stdout = drain(child.stdout.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+    let stderr = drain(child.stderr.take().map(|p| Box::new(p) as Box<dyn Read + Send>));
+
+    let start = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? { break status; }
+        if start.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(IoError::new(std::io::ErrorKind::TimedOut, "child timed out"));
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    };
+    Ok(std::process::Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    })
+}
+
+/* ---------- shebang portability ---------- */
+
+const ENV_SPLIT_SHEBANG: &str = "#!/usr/bin/env -S ";
+
+// `sh` runs the first line as `exec uv run --script "$0" "$@"`; to Python the
+// same line is a bare string literal, so the script itself is unchanged.
+// UNI_UV_BIN wins at run time, else the uv resolved when it was installed.
+fn sh_trampoline(uv: &Path) -> String {
+    let mut quoted = String::new();
+    for c in uv.to_string_lossy().chars() {
+        if matches!(c, '\\' | '"' | '$' | '`') {
+            quoted.push('\\');   // literal inside sh double quotes
+        }
+        quoted.push(c);
+    }
+    format!("#!/bin/sh\n''''exec \"${{UNI_UV_BIN:-{quoted}}}\" run --script \"$0\" \"$@\" # '''\n")
+}
+
+/// Does this machine's `env` understand `-S` (split args)?  coreutils < 8.30
+/// and busybox do not, and exec'ing a `#!/usr/bin/env -S …` script there fails
+/// with a confusing "No such file or directory".
+fn env_split_supported() -> bool {
+    Cmd::new("/usr/bin/env")
+        .args(["-S", "true"])
+        .output()
+        .map(|o| o.status.success())
+        .unwrap_or(false)
+}
+
+/// Rewrite the stored copy's `env -S` shebang into a `/bin/sh` trampoline when
+/// the local `env` can't handle it.  The user's source file is never touched.
+fn fix_shebang(script: &Path) -> Result<(), IoError> {
+    let text = match fs::read_to_string(script) {
+        Ok(t) => t,
+        Err(_) => return Ok(()),                 // binary / non-UTF-8: leave it alone
+    };
+    let Some(first) = text.lines().next() else { return Ok(()) };
+    if !first.starts_with(ENV_SPLIT_SHEBANG) || env_split_supported() {
+        return Ok(());
+    }
+
+    let cmd = first.trim_start_matches(ENV_SPLIT_SHEBANG).trim();
+    if cmd != "uv run --script" {
+        diag!(
+            "⚠️  {}: shebang `{first}` needs `env -S`, which this system lacks; \
+            the plugin may fail to start",
+            script.display()
+        );
+        return Ok(());
+    }
+
+    let rest = &text[first.len()..];
+    let rest = rest.strip_prefix('\n').unwrap_or(rest);
+    fs::write(script, format!("{}{rest}", sh_trampoline(&resolve_uv_bin().value))).ctx("writing", script)?;
+    diag!(
+        "ℹ️  `env -S` unsupported here; rewrote shebang of {} to a /bin/sh trampoline",
+        script.display()
+    );
+    Ok(())
+}
+
+fn remove_plugin(name: &str, purge: bool) -> Result<(), IoError> {
+    for p in removal_targets(name, purge)? {
+        if fs::symlink_metadata(&p).is_ok_and(|md| md.is_dir()) {
+            remove_owned_dir(&p).ctx("removing", &p)?;
+        } else {
+            fs::remove_file(&p).ctx("removing", &p)?;
+        }
+    }
+    Ok(())
+}
+
+/// Everything `remove` would delete for `name`, existing paths only.
+fn removal_targets(name: &str, purge: bool) -> Result<Vec<PathBuf>, IoError> {
+    // a name like `../x` would point the deletes outside dirs we own
+    if Path::new(name).file_name() != Some(std::ffi::OsStr::new(name)) {
+        return Err(IoError::new(std::io::ErrorKind::InvalidInput, format!("invalid plugin name `{name}`")));
+    }
+    let dir = plugin_dir();
+    let mut paths = vec![dir.join(name), dir.join(format!("{}.json", name)), asset_dir(name)];
+    if purge {
+        paths.extend([data_dir(name), history_dir(name), backups_dir(name)]);
+    }
+    // symlink_metadata: a dangling symlink still counts
+    paths.retain(|p| fs::symlink_metadata(p).is_ok());
+    Ok(paths)
+}
+
+/// `remove --stdin`: one name per line.  Unknown or invalid names stop the
+/// batch unless `keep_going`; either way they make the command fail.
+fn remove_from_stdin(purge: bool, dry_run: bool, yes: bool, keep_going: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let input = std::io::read_to_string(std::io::stdin())?;
+    let names: Vec<&str> = input
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .collect();
+    if names.is_empty() {
+        say("no plugin names on stdin");
+        return Ok(());
+    }
+    if !dry_run && !yes && !confirm(&format!("remove {} plugin(s)?", names.len())) {
+        return Err("aborted (pass --yes to skip the prompt)".into());
+    }
+
+    let (mut removed, mut failed) = (0, 0);
+    for name in names {
+        let targets = match removal_targets(name, purge) {
+            Ok(t) if t.is_empty() => Err(format!("no plugin `{name}`")),
+            Ok(t) => Ok(t),
+            Err(e) => Err(e.to_string()),
+        };
+        let result = targets.and_then(|targets| {
+            if dry_run {
+                targets.iter().for_each(|p| println!("{}", p.display()));
+                Ok(())
+            } else {
+                remove_plugin(name, purge).map_err(|e| e.to_string())
+            }
+        });
+        match result {
+            Ok(()) => {
+                removed += 1;
+                if !dry_run {
+                    report(&plugin_dir().join(name), format_args!("Removed plugin `{name}`"));
+                }
+            }
+            Err(e) => {
+                diag!("❌ {e}");
+                failed += 1;
+                if !keep_going {
+                    return Err("stopped at the first failure (use --keep-going to continue)".into());
+                }
+            }
+        }
+    }
+    let verb = if dry_run { "would be removed" } else { "removed" };
+    say(format_args!("{removed} {verb}, {failed} failed"));
+    if failed > 0 {
+        return Err(format!("{failed} plugin(s) could not be removed").into());
+    }
+    if dry_run && removed > 0 {
+        exit(1);   // like `diff`: something would change
+    }
+    Ok(())
+}
+
+/// `uni move`: everything of `name` that lives in `plugin_dir()` goes to the
+/// same place under `to`, manifest untouched.  Its data dir is keyed by name
+/// alone, so it stays where it is.
+fn move_plugin(name: &str, to: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    if read_sidecar(name).is_none() {
+        return Err(format!("no plugin named `{name}`").into());
+    }
+    let dir = plugin_dir();
+    fs::create_dir_all(to).ctx("creating", to)?;
+    if fs::canonicalize(to).ctx("resolving", to)? == fs::canonicalize(&dir).ctx("resolving", &dir)? {
+        return Err(format!("`{name}` is already in {}", to.display()).into());
+    }
+    let paths = [dir.join(name), dir.join(format!("{name}.json")), asset_dir(name), history_dir(name), backups_dir(name)];
+    let moves: Vec<(&PathBuf, PathBuf)> = paths
+        .iter()
+        .filter(|p| fs::symlink_metadata(p).is_ok())
+        .map(|p| (p, to.join(p.strip_prefix(&dir).unwrap())))
+        .collect();
+    if let Some((_, taken)) = moves.iter().find(|(_, dest)| fs::symlink_metadata(dest).is_ok()) {
+        return Err(format!("{} already exists; not moving `{name}`", taken.display()).into());
+    }
+    for (src, dest) in moves {
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent).ctx("creating", parent)?;
+        }
+        move_path(src, &dest)?;
+    }
+    Ok(())
+}
+
+/// Rename `src` to `dest`, or copy and delete when they are on different
+/// filesystems.  Symlinks (`add --link`) are recreated, not followed.
+fn move_path(src: &Path, dest: &Path) -> Result<(), IoError> {
+    match fs::rename(src, dest) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {}
+        r => return r.ctx("moving", src),
+    }
+    let md = fs::symlink_metadata(src).ctx("reading", src)?;
+    if md.file_type().is_symlink() {
+        std::os::unix::fs::symlink(fs::read_link(src).ctx("reading", src)?, dest).ctx("linking", dest)?;
+        return fs::remove_file(src).ctx("removing", src);
+    }
+    if !md.is_dir() {
+        return place_file(src, dest);
+    }
+    fs::create_dir(dest).ctx("creating", dest)?;
+    for entry in fs::read_dir(src).ctx("listing", src)? {
+        let entry = entry.ctx("listing", src)?;
+        move_path(&entry.path(), &dest.join(entry.file_name()))?;
+    }
+    fs::remove_dir(src).ctx("removing", src)
+}
+
+/// Delete a plugin-owned directory; a symlink in its place is unlinked, never followed.
+fn remove_owned_dir(path: &Path) -> Result<(), IoError> {
+    match fs::symlink_metadata(path) {
+        Ok(md) if md.file_type().is_symlink() => fs::remove_file(path),
+        Ok(md) if md.is_dir() => fs::remove_dir_all(path),
+        _ => Ok(()),
+    }
+}
+
+fn list_plugins(health: bool, filter: &ListFilter) -> Result<(), IoError> {
+    // looked up once; a missing uv counts as too old
+    let uv_ok = health && current_uv_version().is_some_and(|v| {
+        cmp_versions(&v, MIN_UV_VERSION).is_some_and(|o| o.is_ge())
+    });
+    let dir = plugin_dir();
+    let entries = match fs::read_dir(&dir) {
+        Ok(rd) => rd,
+        // a fresh install, or a mistyped UNI_PLUGIN_DIR: just nothing installed
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            say("No plugins installed. Try `uni create <name>`.");
+            return Ok(());
+        }
+        Err(e) => return Err(e).ctx("listing", &dir),
+    };
+    let mut any = false;
+    for entry in entries {
+        let p = entry.ctx("listing", &dir)?.path();
+        if p.extension().and_then(|e| e.to_str()) == Some("json") {
+            any = true;
+            let data = fs::read(&p).ctx("reading", &p)?;
+            let m: Manifest = match serde_json::from_slice(&data) {
+                Ok(m) => m,
+                // --health reports a broken sidecar instead of aborting the listing
+                Err(e) if health => {
+                    let stem = p.file_stem().unwrap_or_default().to_string_lossy();
+                    println!("- {stem}  [manifest-invalid]  {}", sidecar_error(&p, &e));
+                    continue;
+                }
+                Err(e) => return Err(IoError::new(std::io::ErrorKind::InvalidData, sidecar_error(&p, &e))),
+            };
+            if !filter.keeps(&m) {
+                continue;
+            }
+            let pin = if m.pinned { "  [pinned]" } else { "" };
+            let hid = if m.hidden { "  [hidden]" } else { "" };
+            let dep = if m.deprecated.is_some() && !health { "  [deprecated]" } else { "" };
+            let status = if health { format!("  [{}]", plugin_health(&m, uv_ok)) } else { String::new() };
+            println!("- {}  (v{}){pin}{hid}{dep}{status}  {}", m.name, m.version, m.description);
+        }
+    }
+    if !any {
+        say("No plugins installed. Try `uni create <name>`.");
+    }
+    Ok(())
+}
+
+/// What `list` narrows the installed set to.
+struct ListFilter {
+    range: VersionRange,
+    no_hidden: bool,
+    tag: Option<String>,
+    grep: Option<Regex>,
+}
+
+impl ListFilter {
+    fn keeps(&self, m: &Manifest) -> bool {
+        if self.no_hidden && m.hidden {
+            return false;
+        }
+        if let Some(t) = &self.tag && !m.tags.contains(t) {
+            return false;
+        }
+        if let Some(re) = &self.grep && !re.is_match(&m.name) && !re.is_match(&m.description) {
+            return false;
+        }
+        match self.range.contains(&m.version) {
+            Some(keep) => keep,
+            // reported, so a range query doesn't silently shrink
+            None => {
+                diag!("⚠️  {}: version {:?} is not comparable, left out", m.name, m.version);
+                false
+            }
+        }
+    }
+}
+
+/// Installed plugins whose script differs from the recorded `sha256`.  Old
+/// installs without a hash (and links) can't drift and are left out.
+fn modified_plugins() -> Vec<Manifest> {
+    let dir = plugin_dir();
+    let mut out: Vec<Manifest> = load_manifests()
+        .into_iter()
+        .filter(|m| {
+            let Some(want) = &m.sha256 else { return false };
+            fs::read(dir.join(&m.name)).is_ok_and(|b| &sha256_hex(&b) != want)
+        })
+        .collect();
+    out.sort_by(|a, b| a.name.cmp(&b.name));
+    out
+}
+
+// placeholders `list --format` understands
+const LIST_FIELDS: &[&str] = &["name", "version", "description", "source", "tags", "pinned", "hidden", "deprecated", "path"];
+
+fn list_field(m: &Manifest, field: &str) -> String {
+    match field {
+        "name"        => m.name.clone(),
+        "version"     => m.version.clone(),
+        "description" => m.description.clone(),
+        "source"      => m.source.clone().unwrap_or_default(),
+        "tags"        => m.tags.join(","),
+        "pinned"      => m.pinned.to_string(),
+        "hidden"      => m.hidden.to_string(),
+        "deprecated"  => m.deprecated.clone().unwrap_or_default(),
+        "path"        => plugin_dir().join(&m.name).display().to_string(),
+        _ => unreachable!("checked by render_template"),
+    }
+}
+
+/// Expand `{field}` placeholders (`{{` / `}}` for literal braces).  Every
+/// placeholder is checked against `LIST_FIELDS`, so typos fail even with no
+/// plugins installed.
+fn render_template(tpl: &str, value: impl Fn(&str) -> String) -> Result<String, String> {
+    let mut out = String::new();
+    let mut rest = tpl;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if let Some(t) = tail.strip_prefix("{{") {
+            out.push('{');
+            rest = t;
+        } else if let Some(t) = tail.strip_prefix("}}") {
+            out.push('}');
+            rest = t;
+        } else if tail.starts_with('}') {
+            return Err(format!("unmatched `}}` in template {tpl:?}"));
+        } else {
+            let end = tail.find('}').ok_or_else(|| format!("unclosed `{{` in template {tpl:?}"))?;
+            let field = &tail[1..end];
+            if !LIST_FIELDS.contains(&field) {
+                return Err(format!("unknown placeholder `{{{field}}}` (known: {})", LIST_FIELDS.join(", ")));
+            }
+            out.push_str(&value(field));
+            rest = &tail[end + 1..];
+        }
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn list_formatted(tpl: &str, mut plugins: Vec<Manifest>) -> Result<(), Box<dyn std::error::Error>> {
+    render_template(tpl, |_| String::new())?;          // validate up front
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    for m in &plugins {
+        println!("{}", render_template(tpl, |f| list_field(m, f))?);
+    }
+    Ok(())
+}
+
+// first uv with PEP 723 inline script metadata, which every template relies on
+const MIN_UV_VERSION: &str = "0.3.0";
+
+/// The most pressing problem with an installed plugin, doctor-style.
+fn plugin_health(m: &Manifest, uv_ok: bool) -> &'static str {
+    if !plugin_dir().join(&m.name).is_file() {
+        "missing-script"
+    } else if !manifest_problems(m).is_empty() {
+        "manifest-invalid"
+    } else if !uv_ok {
+        "uv-too-old"
+    } else if m.deprecated.is_some() {
+        "deprecated"
+    } else {
+        "OK"
+    }
+}
+
+fn show_info(name: &str, raw: bool) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+
+    let meta = plugin_dir().join(format!("{name}.json"));
+    let bytes = fs::read(&meta).map_err(|e| format!("no plugin `{name}` ({}: {e})", meta.display()))?;
+    if raw {
+        // exactly what's on disk, no serde round-trip
+        std::io::stdout().write_all(&bytes)?;
+        return Ok(());
+    }
+
+    let m: Manifest = serde_json::from_slice(&bytes)?;
+    println!("name:        {}", m.name);
+    println!("version:     {}", m.version);
+    println!("description: {}", m.description);
+    if m.pinned {
+        println!("pinned:      yes");
+    }
+    if let Some(note) = &m.deprecated {
+        println!("deprecated:  {note}");
+    }
+    if let Some(min) = &m.min_uni_version {
+        println!("requires:    uni >= {min}");
+    }
+    if !m.aliases.is_empty() {
+        println!("aliases:     {}", m.aliases.join(", "));
+    }
+    if !m.tags.is_empty() {
+        println!("tags:        {}", m.tags.join(", "));
+    }
+    if !m.requires_plugins.is_empty() {
+        println!("needs:       {}", m.requires_plugins.join(", "));
+    }
+    if let Some(src) = &m.source {
+        println!("source:      {src}");
+    }
+    let script = plugin_dir().join(&m.name);
+    match fs::read_link(&script) {
+        Ok(target) if m.linked => println!("script:      {} → {}", script.display(), target.display()),
+        _ => println!("script:      {}", script.display()),
+    }
+    if !m.commands.is_empty() {
+        println!("commands:");
+        for c in &m.commands {
+            println!("  {:<12} {}", c.name, c.description);
+        }
+    }
+    if !m.extra.is_empty() {
+        println!("extra:");
+        for (k, v) in &m.extra {
+            println!("  {k:<12} {v}");
+        }
+    }
+    Ok(())
+}
+
+/* ---------- history / rollback ---------- */
+
+// replaced versions kept per plugin; older ones are dropped on the next backup
+const HISTORY_KEEP: usize = 3;
+
+// a dot-dir, so export/orphan scans (files only) and `list` never see it
+fn history_dir(name: &str) -> PathBuf {
+    plugin_dir().join(".history").join(name)
+}
+
+/// Copy the installed script + sidecar of `name` (if any) into a fresh entry
+/// under `history_dir`, named by install time in nanoseconds so it sorts.
+fn backup_plugin(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let dir = plugin_dir();
+    let (script, meta) = (dir.join(name), dir.join(format!("{name}.json")));
+    if !script.is_file() || !meta.is_file() {
+        return Ok(());
+    }
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos();
+    let entry = history_dir(name).join(format!("{nanos:020}"));
+    fs::create_dir_all(&entry).ctx("creating", &entry)?;
+    fs::copy(&script, entry.join(name)).ctx("backing up", &script)?;              // keeps the mode bits
+    fs::copy(&meta, entry.join(format!("{name}.json"))).ctx("backing up", &meta)?;
+
+    for (old, _) in history(name).into_iter().skip(HISTORY_KEEP) {
+        fs::remove_dir_all(&old).ctx("pruning", &old)?;
+    }
+    Ok(())
+}
+
+/// Kept versions of `name`, newest first.  Entries with an unreadable
+/// sidecar are skipped.
+fn history(name: &str) -> Vec<(PathBuf, Manifest)> {
+    let mut out: Vec<(PathBuf, Manifest)> = fs::read_dir(history_dir(name))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| {
+            let p = e.path();
+            let bytes = fs::read(p.join(format!("{name}.json"))).ok()?;
+            Some((p, serde_json::from_slice(&bytes).ok()?))
+        })
+        .collect();
+    out.sort_by(|a, b| b.0.cmp(&a.0));
+    out
+}
+
+// entry dirs are named by nanoseconds since the epoch
+fn history_time(entry: &Path) -> String {
+    let nanos: u128 = entry.file_name().and_then(|n| n.to_str()).and_then(|n| n.parse().ok()).unwrap_or(0);
+    rfc3339((nanos / 1_000_000_000) as u64)
+}
+
+/// Put the most recent kept version back in place and drop it from the history.
+fn rollback_plugin(name: &str) -> Result<Manifest, Box<dyn std::error::Error>> {
+    if Path::new(name).file_name() != Some(std::ffi::OsStr::new(name)) {
+        return Err(format!("invalid plugin name `{name}`").into());
+    }
+    let (entry, m) = history(name)
+        .into_iter()
+        .next()
+        .ok_or_else(|| format!("no kept versions of `{name}` to roll back to"))?;
+
+    let staging = tempfile::tempdir()?;
+    let staged = staging.path().join(name);
+    fs::copy(entry.join(name), &staged).ctx("restoring", &entry)?;
+    place_file(&staged, &plugin_dir().join(name))?;
+    write_sidecar(&m)?;
+    fs::remove_dir_all(&entry).ctx("removing", &entry)?;
+    Ok(m)
+}
+
+fn show_history(name: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let kept = history(name);
+    if kept.is_empty() {
+        println!("no kept versions of `{name}`");
+    }
+    for (entry, m) in kept {
+        println!("- v{:<10} replaced {}", m.version, history_time(&entry));
+    }
+    Ok(())
+}
+
+// `add --overwrite-backup` archives: unlike `.history`, visible and never pruned
+fn backups_dir(name: &str) -> PathBuf {
+    plugin_dir().join("backups").join(name)
+}
+
+/// Copy the installed `name` into a new `backups_dir` entry named by the
+/// UTC time (`2026-10-14T093000Z`); nothing to do if it isn't installed.
+fn archive_plugin(name: &str) -> Result<Option<(PathBuf, Manifest)>, Box<dyn std::error::Error>> {
+    let dir = plugin_dir();
+    let (script, meta) = (dir.join(name), dir.join(format!("{name}.json")));
+    let Some(m) = read_sidecar(name).filter(|_| script.is_file()) else { return Ok(None) };
+
+    let stamp = rfc3339(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs()).replace(':', "");
+    let base = backups_dir(name);
+    let mut entry = base.join(&stamp);
+    for n in 2.. {
+        if !entry.exists() { break; }
+        entry = base.join(format!("{stamp}-{n}"));     // two replacements within a second
+    }
+    fs::create_dir_all(&entry).ctx("creating", &entry)?;
+    fs::copy(&script, entry.join(name)).ctx("backing up", &script)?;
+    fs::copy(&meta, entry.join(format!("{name}.json"))).ctx("backing up", &meta)?;
+    Ok(Some((entry, m)))
+}
+
+/// Kept copies of `name`, newest first.
+fn backups(name: &str) -> Vec<(PathBuf, Manifest)> {
+    let mut out: Vec<(PathBuf, Manifest)> = fs::read_dir(backups_dir(name))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| {
+            let p = e.path();
+            let bytes = fs::read(p.join(format!("{name}.json"))).ok()?;
+            Some((p, serde_json::from_slice(&bytes).ok()?))
+        })
+        .collect();
+    out.sort_by(|a, b| b.0.cmp(&a.0));
+    out
+}
+
+/// Reinstall a kept copy (the newest, or the one named `from`).  The copy
+/// stays in `backups/`; what it replaces goes to the rollback history.
+fn restore_plugin(name: &str, from: Option<&str>) -> Result<Manifest, Box<dyn std::error::Error>> {
+    if Path::new(name).file_name() != Some(std::ffi::OsStr::new(name)) {
+        return Err(format!("invalid plugin name `{name}`").into());
+    }
+    let kept = backups(name);
+    let (entry, m) = match from {
+        Some(t) => kept.into_iter().find(|(p, _)| p.file_name().is_some_and(|n| n == t))
+            .ok_or_else(|| format!("no backup `{t}` of `{name}` (see `uni restore {name} --list`)"))?,
+        None => kept.into_iter().next().ok_or_else(|| format!("no backups of `{name}`"))?,
+    };
+
+    backup_plugin(name)?;
+    let staging = tempfile::tempdir()?;
+    let staged = staging.path().join(name);
+    fs::copy(entry.join(name), &staged).ctx("restoring", &entry)?;
+    place_file(&staged, &plugin_dir().join(name))?;
+    write_sidecar(&m)?;
+    Ok(m)
+}
+
+fn show_backups(name: &str) {
+    let kept = backups(name);
+    if kept.is_empty() {
+        println!("no backups of `{name}`");
+    }
+    for (entry, m) in kept {
+        println!("- {}  v{}", entry.file_name().unwrap_or_default().to_string_lossy(), m.version);
+    }
+}
+
+/* ---------- diff ---------- */
+
+// sidecar keys written by `uni` itself; never part of what the plugin declares
+const SIDECAR_ONLY_KEYS: &[&str] = &["source", "pinned", "extra", "linked", "sha256", "manifest_origin", "executable", "custom_description", "no_exec"];
+
+/// Minimal unified diff (LCS based, 3 lines of context).  Plugin scripts are
+/// small enough that the O(n·m) table is no concern.
+fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    const CONTEXT: usize = 3;
+    let a: Vec<&str> = old.lines().collect();
+    let b: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] = LCS length of a[i..] and b[j..]
+    let mut lcs = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    // edit script: (tag, old line no, new line no, text)
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((' ', i, j, a[i])); i += 1; j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push(('-', i, j, a[i])); i += 1;
+        } else {
+            ops.push(('+', i, j, b[j])); j += 1;
+        }
+    }
+    if ops.iter().all(|op| op.0 == ' ') {
+        return String::new();
+    }
+
+    let mut out = format!("--- {old_name}\n+++ {new_name}\n");
+    let mut k = 0;
+    while k < ops.len() {
+        let Some(first) = ops[k..].iter().position(|op| op.0 != ' ') else { break };
+        let start = (k + first).saturating_sub(CONTEXT);
+        // extend the hunk while changes are within 2·CONTEXT of each other
+        let mut end = k + first;
+        let mut last_change = end;
+        while end < ops.len() {
+            if ops[end].0 != ' ' { last_change = end; }
+            if end - last_change > 2 * CONTEXT { break; }
+            end += 1;
+        }
+        let end = (last_change + CONTEXT + 1).min(ops.len());
+        let hunk = &ops[start..end];
+        let old_len = hunk.iter().filter(|op| op.0 != '+').count();
+        let new_len = hunk.iter().filter(|op| op.0 != '-').count();
+        out += &format!(
+            "@@ -{},{old_len} +{},{new_len} @@\n",
+            hunk[0].1 + usize::from(old_len > 0),
+            hunk[0].2 + usize::from(new_len > 0),
+        );
+        for (tag, _, _, text) in hunk {
+            out += &format!("{tag}{text}\n");
+        }
+        k = end;
+    }
+    out
+}
+
+fn manifest_json(m: &Manifest) -> Result<String, serde_json::Error> {
+    let mut v = serde_json::to_value(m)?;
+    if let Some(obj) = v.as_object_mut() {
+        for key in SIDECAR_ONLY_KEYS { obj.remove(*key); }
+    }
+    serde_json::to_string_pretty(&v)
+}
+
+fn diff_plugin(name: &str, script: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let installed = plugin_dir().join(name);
+    let current = read_sidecar(name).ok_or_else(|| format!("no plugin `{name}`"))?;
+    let incoming = fetch_manifest(script, &InstallOpts::default())?;
+
+    let old_src = fs::read_to_string(&installed).ctx("reading", &installed)?;
+    let new_src = fs::read_to_string(script).ctx("reading", script)?;
+    let script_diff = unified_diff(&old_src, &new_src, &installed.display().to_string(), &script.display().to_string());
+    let manifest_diff = unified_diff(
+        &manifest_json(&current)?,
+        &manifest_json(&incoming)?,
+        &format!("{name}.json (installed)"),
+        &format!("{name}.json (incoming)"),
+    );
+
+    if script_diff.is_empty() && manifest_diff.is_empty() {
+        println!("✅ {} is identical to the installed `{name}`", script.display());
+        return Ok(());
+    }
+    print!("{script_diff}");
+    print!("{manifest_diff}");
+    Ok(())
+}
+
+/* ---------- dynamic completion ---------- */
+
+/// Candidates for the last of `words`, computed against the live CLI tree, so
+/// newly added plugins and their subcommands complete without regenerating
+/// any script.
+fn complete(words: &[String]) -> Vec<String> {
+    let root = build_cli();
+    let (current, done) = words.split_last().map(|(c, d)| (c.as_str(), d)).unwrap_or(("", &[]));
+
+    let mut node = &root;
+    let mut skip_value = false;
+    for w in done {
+        if skip_value { skip_value = false; continue; }
+        if let Some(long) = w.strip_prefix("--") {
+            skip_value = !long.contains('=')
+                && node.get_arguments().any(|a| a.get_long() == Some(long) && a.get_action().takes_values());
+            continue;
+        }
+        if w.starts_with('-') { continue; }
+        match node.find_subcommand(w) {
+            Some(sub) => node = sub,
+            None => return Vec::new(),            // inside a plugin's own args
+        }
+    }
+
+    let mut out: Vec<String> = if current.starts_with('-') {
+        node.get_arguments()
+            .filter(|a| !a.is_hide_set())
+            .filter_map(|a| a.get_long().map(|l| format!("--{l}")))
+            .collect()
+    } else {
+        node.get_subcommands()
+            .filter(|c| !c.is_hide_set())
+            .map(|c| c.get_name().to_owned())
+            .collect()
+    };
+    out.retain(|c| c.starts_with(current));
+    out.sort();
+    out
+}
+
+fn completion_script(shell: &str) -> &'static str {
+    match shell {
+        "bash" => r#"_uni_complete() {
+    local IFS=$'\n'
+    COMPREPLY=($(uni __complete -- "${COMP_WORDS[@]:1:COMP_CWORD}"))
+}
+complete -o default -F _uni_complete uni
+"#,
+        "zsh" => r#"_uni_complete() {
+    local -a candidates
+    candidates=("${(@f)$(uni __complete -- "${words[@]:1:CURRENT-1}")}")
+    compadd -a candidates
+}
+compdef _uni_complete uni
+"#,
+        _ => r#"complete -c uni -f -a '(uni __complete -- (commandline -opc)[2..] (commandline -ct))'
+"#,
+    }
+}
+
+/* ---------- interactive shell ---------- */
+
+/// `uni shell`: read a line, run it as `uni <line>` in a fresh process, repeat.
+/// A child per line means plugins added or removed mid-session show up
+/// without restarting, and every line behaves exactly like a one-shot call.
+fn run_shell() -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::IsTerminal;
+
+    let exe = std::env::current_exe()?;
+    let interactive = std::io::stdin().is_terminal();
+    if interactive {
+        say("uni shell: <TAB> completes, `exit` or Ctrl-D leaves");
+    }
+    loop {
+        let line = if interactive { read_line_raw("uni> ")? } else { read_line_plain()? };
+        let Some(line) = line else { break };
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') { continue; }
+        if matches!(line, "exit" | "quit") { break; }
+        let Some(words) = shlex::split(line) else {
+            diag!("unbalanced quotes");
+            continue;
+        };
+        let status = Cmd::new(&exe).args(shell_global_args()).args(&words).status()?;
+        if !status.success() && !interactive {
+            return Err(format!("`{line}` failed ({status})").into());
+        }
+    }
+    Ok(())
+}
+
+// the globals `uni shell` itself got, passed on to every line
+fn shell_global_args() -> Vec<String> {
+    let g = global_flags();
+    let mut args = Vec::new();
+    if g.offline { args.push("--offline".into()); }
+    if g.verbose { args.push("--verbose".into()); }
+    if g.quiet   { args.push("--quiet".into()); }
+    if let Some(c) = g.color {
+        args.push(format!("--color={}", match c { ColorMode::Auto => "auto", ColorMode::Always => "always", ColorMode::Never => "never" }));
+    }
+    if let Some(p) = &g.profile { args.push(format!("--profile={p}")); }
+    if let Some(Some(c)) = CONFIG_FLAG.get() { args.push(format!("--config={}", c.display())); }
+    args
+}
+
+fn read_line_plain() -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    Ok((std::io::stdin().read_line(&mut line)? > 0).then_some(line))
+}
+
+/// Terminal back in cooked mode when dropped, however the editor exits.
+struct RawMode(libc::termios);
+
+impl RawMode {
+    fn enable() -> std::io::Result<Self> {
+        // SAFETY: plain termios calls on stdin with a zeroed, then filled struct
+        unsafe {
+            let mut t: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut t) != 0 {
+                return Err(IoError::last_os_error());
+            }
+            let saved = RawMode(t);
+            t.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG);
+            t.c_cc[libc::VMIN] = 1;
+            t.c_cc[libc::VTIME] = 0;
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &t) != 0 {
+                return Err(IoError::last_os_error());
+            }
+            Ok(saved)
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // SAFETY: restores the attributes read in `enable`
+        unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.0); }
+    }
+}
+
+/// Minimal line editor: typing, backspace, Ctrl-U, Ctrl-C (drop the line),
+/// Ctrl-D on an empty line (`None`), and <TAB> through `complete`.
+fn read_line_raw(prompt: &str) -> std::io::Result<Option<String>> {
+    use std::io::{Read, Write};
+
+    let _raw = RawMode::enable()?;
+    let mut out = std::io::stdout();
+    let mut buf: Vec<u8> = Vec::new();
+    write!(out, "{prompt}")?;
+    out.flush()?;
+
+    let mut stdin = std::io::stdin().lock();
+    let mut byte = [0u8; 1];
+    loop {
+        if stdin.read(&mut byte)? == 0 {
+            return Ok(None);
+        }
+        match byte[0] {
+            b'\r' | b'\n' => {
+                writeln!(out)?;
+                return Ok(Some(String::from_utf8_lossy(&buf).into_owned()));
+            }
+            0x04 if buf.is_empty() => {
+                writeln!(out)?;
+                return Ok(None);
+            }
+            0x03 => {
+                buf.clear();
+                write!(out, "^C\n{prompt}")?;
+            }
+            0x15 => {
+                buf.clear();
+                write!(out, "\r\x1b[K{prompt}")?;
+            }
+            0x7f | 0x08 => {
+                // drop one whole UTF-8 character
+                while let Some(b) = buf.pop() {
+                    if b & 0xC0 != 0x80 { break; }
+                }
+                write!(out, "\r\x1b[K{prompt}{}", String::from_utf8_lossy(&buf))?;
+            }
+            b'\t' => {
+                let line = String::from_utf8_lossy(&buf).into_owned();
+                let (done, shown) = shell_complete(&line);
+                if !shown.is_empty() {
+                    write!(out, "\n{}\n", shown.join("  "))?;
+                }
+                buf = done.into_bytes();
+                write!(out, "\r\x1b[K{prompt}{}", String::from_utf8_lossy(&buf))?;
+            }
+            0x1b => {
+                // arrow keys etc.: swallow `ESC [ ... final`
+                let mut seq = [0u8; 1];
+                if stdin.read(&mut seq)? == 1 && seq[0] == b'[' {
+                    while stdin.read(&mut seq)? == 1 && !(0x40..=0x7e).contains(&seq[0]) {}
+                }
+            }
+            b if b >= 0x20 || b & 0x80 != 0 => {
+                buf.push(b);
+                out.write_all(&byte)?;
+            }
+            _ => {}
+        }
+        out.flush()?;
+    }
+}
+
+/// Complete the last word of `line`.  Returns the new line and, when several
+/// candidates remain, the list to show.
+fn shell_complete(line: &str) -> (String, Vec<String>) {
+    let mut words: Vec<String> = line.split_whitespace().map(str::to_owned).collect();
+    if line.is_empty() || line.ends_with(' ') {
+        words.push(String::new());
+    }
+    let current = words.last().cloned().unwrap_or_default();
+    let candidates = complete(&words);
+    let head = &line[..line.len() - current.len()];
+    match candidates.as_slice() {
+        [] => (line.to_owned(), Vec::new()),
+        [only] => (format!("{head}{only} "), Vec::new()),
+        many => {
+            // extend to the longest shared prefix
+            let mut prefix = many[0].clone();
+            for c in &many[1..] {
+                while !c.starts_with(&prefix) { prefix.pop(); }
+            }
+            (format!("{head}{prefix}"), many.to_vec())
+        }
+    }
+}
+
+/* ---------- call ---------- */
+
+/// `uni call <name> <request>` input: `{"subcommand": "run", "args": ["--force"]}`.
+/// All keys are optional; unknown keys are rejected.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CallRequest {
+    #[serde(default)]
+    subcommand: Option<String>,
+    #[serde(default)]
+    args: Vec<String>,
+    // fed to the plugin's stdin; without it the plugin reads EOF, never
+    // uni's own stdin (output is captured, so nothing could answer a prompt)
+    #[serde(default)]
+    stdin: Option<String>,
+}
+
+/// `uni call` output, one JSON object on stdout.  This shape is stable:
+/// fields may be added, never renamed or removed.
+///
+/// - `exit_code`: the plugin's exit status, `null` if killed by a signal
+/// - `signal`: the signal number when killed, else `null`
+/// - `stdout` / `stderr`: captured output, lossily decoded as UTF-8
+#[derive(Serialize)]
+struct CallResponse {
+    plugin: String,
+    version: String,
+    exit_code: Option<i32>,
+    signal: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+fn call_plugin(name: &str, request: &str) -> Result<(), Box<dyn std::error::Error>> {
+    use std::os::unix::process::ExitStatusExt;
+
+    let req: CallRequest = serde_json::from_str(request).map_err(|e| format!("bad call request: {e}"))?;
+    let m = load_manifests()
+        .into_iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| format!("no plugin `{name}`"))?;
+    if let Some(p) = uni_version_problem(&m) {
+        return Err(p.into());
+    }
+    if let Some(sub) = &req.subcommand
+        && !m.allow_any_subcommand
+        && !m.dynamic_commands
+        && !m.commands.is_empty()
+        && !m.commands.iter().any(|c| &c.name == sub)
+    {
+        return Err(format!("`{name}` has no subcommand `{sub}`").into());
+    }
+
+    use std::process::Stdio;
+    let mut child = plugin_command(Some(&m), &plugin_dir().join(name))
+        .args(req.subcommand.iter())
+        .args(&req.args)
+        .stdin(if req.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // written from a thread: a plugin that prints before reading would
+    // otherwise fill its stdout pipe while we're blocked on its stdin
+    let feeder = child.stdin.take().zip(req.stdin).map(|(mut pipe, input)| {
+        std::thread::spawn(move || {
+            use std::io::Write;
+            let _ = pipe.write_all(input.as_bytes());   // EPIPE: the plugin didn't want it all
+        })
+    });
+    let out = child.wait_with_output()?;
+    if let Some(t) = feeder {
+        let _ = t.join();
+    }
+    let resp = CallResponse {
+        plugin: m.name.clone(),
+        version: m.version.clone(),
+        exit_code: out.status.code(),
+        signal: out.status.signal(),
+        stdout: String::from_utf8_lossy(&out.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&out.stderr).into_owned(),
+    };
+    println!("{}", serde_json::to_string(&resp)?);
+    Ok(())
+}
+
+/* ---------- bench ---------- */
+
+fn bench_plugin(name: &str, argv: &[std::ffi::OsString], runs: usize, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    use std::process::Stdio;
+
+    let manifest = read_sidecar(name).ok_or_else(|| format!("no plugin `{name}`"))?;
+    let script = plugin_dir().join(name);
+    let run_once = || -> Result<Duration, Box<dyn std::error::Error>> {
+        let started = Instant::now();
+        let status = plugin_command(Some(&manifest), &script)
+            .args(argv)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()?;
+        let took = started.elapsed();
+        if !status.success() {
+            diag!("⚠️  run exited with {status}");
+        }
+        Ok(took)
+    };
+
+    run_once()?;                                      // warmup: fills uv's cache
+    let mut times = (0..runs.max(1)).map(|_| run_once()).collect::<Result<Vec<_>, _>>()?;
+    times.sort();
+    let (min, median, max) = (times[0], times[times.len() / 2], times[times.len() - 1]);
+
+    if json {
+        let ms = |d: Duration| (d.as_secs_f64() * 1_000_000.0).round() / 1000.0;
+        let report = serde_json::json!({
+            "plugin": name,
+            "runs": times.len(),
+            "min_ms": ms(min),
+            "median_ms": ms(median),
+            "max_ms": ms(max),
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{name}: {} runs (after 1 warmup)", times.len());
+        println!("  min     {}", fmt_duration(min));
+        println!("  median  {}", fmt_duration(median));
+        println!("  max     {}", fmt_duration(max));
+    }
+    Ok(())
+}
+
+/* ---------- doctor ---------- */
+
+fn confirm(prompt: &str) -> bool {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    print!("{prompt} [y/N] ");
+    let _ = std::io::stdout().flush();
+    let mut line = String::new();
+    // stdin may be carrying data (`remove --stdin`); ask the terminal instead
+    let read = if std::io::stdin().is_terminal() {
+        std::io::stdin().read_line(&mut line)
+    } else {
+        fs::File::open("/dev/tty").and_then(|tty| std::io::BufReader::new(tty).read_line(&mut line))
+    };
+    if read.is_err() { return false; }
+    matches!(line.trim(), "y" | "Y" | "yes")
+}
+
+/// Scripts without a sidecar, and sidecars without a script.
+fn find_orphans() -> Result<(Vec<PathBuf>, Vec<PathBuf>), IoError> {
+    let dir = plugin_dir();
+    let mut scripts = Vec::new();
+    let mut sidecars = Vec::new();
+    for entry in fs::read_dir(&dir).ctx("listing", &dir)? {
+        let p = entry.ctx("listing", &dir)?.path();
+        if !p.is_file() { continue; }                  // `<name>.d` asset dirs
+        let name = p.file_name().unwrap().to_string_lossy().into_owned();
+        match name.strip_suffix(".json") {
+            // a dangling `add --link` symlink is the links check's business
+            Some(stem) if fs::symlink_metadata(dir.join(stem)).is_err() => sidecars.push(p),
+            Some(_) => {}
+            None if !dir.join(format!("{name}.json")).is_file() => scripts.push(p),
+            None => {}
+        }
+    }
+    Ok((scripts, sidecars))
+}
+
+/// `path:line:column: message` for a sidecar serde_json rejected, so an
+/// editor can jump straight to the spot.
+fn sidecar_error(path: &Path, e: &serde_json::Error) -> String {
+    let msg = e.to_string();
+    let suffix = format!(" at line {} column {}", e.line(), e.column());
+    let msg = msg.strip_suffix(&suffix).unwrap_or(&msg);
+    format!("{}:{}:{}: {msg}", path.display(), e.line(), e.column())
+}
+
+/// Every sidecar in `plugin_dir()` that doesn't parse, as `sidecar_error` lines.
+fn broken_sidecars() -> Result<Vec<String>, IoError> {
+    let dir = plugin_dir();
+    let mut broken = Vec::new();
+    for entry in fs::read_dir(&dir).ctx("listing", &dir)? {
+        let p = entry.ctx("listing", &dir)?.path();
+        if p.extension().is_none_or(|e| e != "json") || !p.is_file() { continue; }
+        let data = fs::read(&p).ctx("reading", &p)?;
+        if let Err(e) = serde_json::from_slice::<Manifest>(&data) {
+            broken.push(sidecar_error(&p, &e));
+        }
+    }
+    broken.sort();
+    Ok(broken)
+}
+
+/// Linked plugins whose symlink no longer resolves, with the missing target.
+fn broken_links() -> Result<Vec<(String, PathBuf)>, IoError> {
+    let dir = plugin_dir();
+    let mut broken = Vec::new();
+    for entry in fs::read_dir(&dir).ctx("listing", &dir)? {
+        let p = entry.ctx("listing", &dir)?.path();
+        if p.extension().is_none_or(|e| e != "json") { continue; }
+        let Some(m) = fs::read(&p).ok().and_then(|b| serde_json::from_slice::<Manifest>(&b).ok()) else { continue };
+        let link = dir.join(&m.name);
+        if m.linked && !link.exists() && let Ok(target) = fs::read_link(&link) {
+            broken.push((m.name, target));
+        }
+    }
+    broken.sort();
+    Ok(broken)
+}
+
+/// `uni prune`: the orphans `doctor` reports, deleted in one go after a
+/// single confirmation.
+fn prune(dry_run: bool, yes: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let (scripts, sidecars) = find_orphans()?;
+    if scripts.is_empty() && sidecars.is_empty() {
+        say("✅ nothing to prune");
+        return Ok(());
+    }
+    for p in scripts.iter().chain(&sidecars) {
+        println!("{}", p.display());
+    }
+    let counts = format!("{} script(s) without a manifest, {} manifest(s) without a script", scripts.len(), sidecars.len());
+    if dry_run {
+        say(format_args!("would delete {counts}"));
+        return Ok(());
+    }
+    if !yes && !confirm(&format!("delete {counts}?")) {
+        return Err("aborted; nothing deleted".into());
+    }
+    for p in scripts.iter().chain(&sidecars) {
+        fs::remove_file(p).ctx("deleting", p)?;
+    }
+    say(format_args!("deleted {counts}"));
+    Ok(())
+}
+
+/* ---------- audit ---------- */
+
+const AUDITED_OPS: &[&str] = &["add", "remove", "move", "import", "export", "rollback", "restore", "pin", "unpin", "set", "unset", "prune", "sync"];
+
+/// One line of the audit file.
+#[derive(Serialize, Deserialize)]
+struct AuditEntry {
+    time: String,
+    op: String,
+    plugins: Vec<AuditPlugin>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<String>,
+    // "ok", or the error message
+    outcome: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AuditPlugin {
+    name: String,
+    // installed afterwards; unset once removed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    // installed before, if the command replaced or removed it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    previous: Option<String>,
+}
+
+// version, and the hash to catch a reinstall of the same version
+fn installed_versions() -> std::collections::BTreeMap<String, (String, Option<String>)> {
+    load_manifests().into_iter().map(|m| (m.name, (m.version, m.sha256))).collect()
+}
+
+/// A command result that names the plugin it touched, so an identical
+/// reinstall (nothing on disk changes) still shows up in the audit entry.
+trait AuditSubject {
+    fn audit_name(&self) -> Option<&str> { None }
+}
+impl AuditSubject for () {}
+impl AuditSubject for Manifest {
+    fn audit_name(&self) -> Option<&str> { Some(&self.name) }
+}
+
+/// Run the mutating command `f` and append what it did to the audit file:
+/// the plugins in `named` plus any that were installed, replaced or removed.  A
+/// failed append only warns; the command's own result is what counts.
+fn audited<T: AuditSubject>(
+    op: &str,
+    named: &[String],
+    source: Option<String>,
+    f: impl FnOnce() -> Result<T, Box<dyn std::error::Error>>,
+) -> Result<T, Box<dyn std::error::Error>> {
+    let before = installed_versions();
+    let result = f();
+    let after = installed_versions();
+
+    let mut names: std::collections::BTreeSet<String> = named.iter().cloned().collect();
+    names.extend(result.as_ref().ok().and_then(T::audit_name).map(str::to_owned));
+    names.extend(before.keys().chain(after.keys()).filter(|n| before.get(*n) != after.get(*n)).cloned());
+    let plugins = names
+        .into_iter()
+        .map(|n| AuditPlugin {
+            version: after.get(&n).map(|(v, _)| v.clone()),
+            previous: before.get(&n).filter(|old| after.get(&n) != Some(*old)).map(|(v, _)| v.clone()),
+            name: n,
+        })
+        .collect();
+    let entry = AuditEntry {
+        time: timestamp(),
+        op: op.into(),
+        plugins,
+        source,
+        outcome: result.as_ref().map_or_else(|e| e.to_string(), |_| "ok".into()),
+    };
+    if let Err(e) = append_audit(&entry) {
+        diag!("⚠️  could not write the audit entry: {e}");
+    }
+    result
+}
+
+fn append_audit(entry: &AuditEntry) -> Result<(), Box<dyn std::error::Error>> {
+    use std::io::Write;
+    let path = resolve_audit_file().value;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).ctx("creating", dir)?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    // one write per line, so concurrent `uni`s don't interleave
+    fs::OpenOptions::new().create(true).append(true).open(&path).ctx("opening", &path)?
+        .write_all(line.as_bytes()).ctx("appending to", &path)?;
+    Ok(())
+}
+
+fn show_audit(ops: &[String], tail: Option<usize>, json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let path = resolve_audit_file().value;
+    let text = match fs::read_to_string(&path) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        r => r.ctx("reading", &path)?,
+    };
+    let mut entries = Vec::new();
+    for (n, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+        match serde_json::from_str::<AuditEntry>(line) {
+            Ok(e) if ops.is_empty() || ops.contains(&e.op) => entries.push((line, e)),
+            Ok(_) => {}
+            Err(e) => diag!("⚠️  {}:{}: {e}", path.display(), n + 1),
+        }
+    }
+    let skip = tail.map_or(0, |t| entries.len().saturating_sub(t));
+    for (line, e) in entries.into_iter().skip(skip) {
+        if json {
+            println!("{line}");
+            continue;
+        }
+        let plugins: Vec<String> = e.plugins.iter().map(|p| match (&p.previous, &p.version) {
+            (Some(old), Some(new)) => format!("{} v{old} → v{new}", p.name),
+            (Some(old), None) => format!("{} v{old} (removed)", p.name),
+            (None, Some(v)) => format!("{} v{v}", p.name),
+            (None, None) => p.name.clone(),
+        }).collect();
+        let dir = if matches!(e.op.as_str(), "export" | "move") { "to" } else { "from" };
+        let source = e.source.map(|s| format!(" {dir} {s}")).unwrap_or_default();
+        let outcome = if e.outcome == "ok" { "✅".to_string() } else { format!("❌ {}", e.outcome) };
+        println!("{}  {:<8} {}{source}  {outcome}", e.time, e.op, plugins.join(", "));
+    }
+    Ok(())
+}
+
+/// How a `doctor` check came out; `warn` and `fail` both count as problems.
+#[derive(Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    Ok,
+    Fixed,
+    Warn,
+    Fail,
+}
+
+#[derive(Serialize)]
+struct CheckResult {
+    check: &'static str,
+    status: CheckStatus,
+    message: String,
+}
+
+/// `doctor`'s findings: printed as they come, or with `--json` kept for one
+/// report at the end.
+struct DoctorReport {
+    json: bool,
+    checks: Vec<CheckResult>,
+}
+
+impl DoctorReport {
+    fn add(&mut self, check: &'static str, status: CheckStatus, message: String) {
+        if !self.json {
+            match status {
+                CheckStatus::Ok    => println!("✅ {message}"),
+                CheckStatus::Fixed => println!("🔧 {message}"),
+                CheckStatus::Warn  => println!("⚠️  {message}"),
+                CheckStatus::Fail  => println!("❌ {message}"),
+            }
+        }
+        self.checks.push(CheckResult { check, status, message });
+    }
+
+    /// A check that found nothing, which the text output doesn't mention.
+    fn clean(&mut self, check: &'static str, message: &str) {
+        if self.json && !self.checks.iter().any(|c| c.check == check) {
+            self.checks.push(CheckResult { check, status: CheckStatus::Ok, message: message.into() });
+        }
+    }
+
+    fn problems(&self) -> usize {
+        self.checks.iter().filter(|c| matches!(c.status, CheckStatus::Warn | CheckStatus::Fail)).count()
+    }
+
+    fn finish(self, hint: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let problems = self.problems();
+        if self.json {
+            #[derive(Serialize)]
+            struct Report<'a> { ok: bool, checks: &'a [CheckResult] }
+            println!("{}", serde_json::to_string_pretty(&Report { ok: problems == 0, checks: &self.checks })?);
+        }
+        if problems > 0 {
+            return Err(format!("{problems} problem(s) found{hint}").into());
+        }
+        if !self.json {
+            println!("🎉 all good");
+        }
+        Ok(())
+    }
+}
+
+fn doctor(fix: bool, yes: bool, skip: &[String], json: bool) -> Result<(), Box<dyn std::error::Error>> {
+    use CheckStatus::{Fail, Fixed, Ok as Pass, Warn};
+    let skipped = |check: &str| skip.iter().any(|s| s == check);
+    let mut report = DoctorReport { json, checks: Vec::new() };
+
+    // 1. plugin dir
+    let dir = plugin_dir();
+    if !skipped("plugin-dir") {
+        if dir.is_dir() {
+            report.add("plugin-dir", Pass, format!("plugin dir {}", dir.display()));
+        } else if fix {
+            ensure_plugin_dir()?;
+            report.add("plugin-dir", Fixed, format!("created plugin dir {}", dir.display()));
+        } else {
+            report.add("plugin-dir", Fail, format!("plugin dir {} is missing", dir.display()));
+        }
+    }
+
+    // 2. uv
+    if !skipped("uv") {
+        match current_uv_version() {
+            Some(v) => report.add("uv", Pass, format!("uv {v}")),
+            None if fix => {
+                if !json {
+                    println!("🔧 installing uv …");
+                }
+                match install_uv() {
+                    Ok(_)  => report.add("uv", Fixed, "uv installed".into()),
+                    Err(e) => report.add("uv", Fail, format!("uv install failed: {e}")),
+                }
+            }
+            None => report.add("uv", Fail, "uv not found on PATH".into()),
+        }
+    }
+
+    // the rest look inside the plugin dir
+    if !dir.is_dir() {
+        return report.finish("");
+    }
+
+    // 3. orphans (destructive: prompt unless --yes; --json never prompts)
+    if !skipped("orphans") {
+        let (scripts, sidecars) = find_orphans()?;
+        if scripts.is_empty() && sidecars.is_empty() {
+            report.add("orphans", Pass, "no orphaned scripts or manifests".into());
+        }
+        for p in scripts.iter().chain(&sidecars) {
+            let what = if p.extension().is_some_and(|e| e == "json") { "manifest without script" } else { "script without manifest" };
+            if fix && (yes || (!json && confirm(&format!("delete orphan {}?", p.display())))) {
+                fs::remove_file(p).ctx("deleting", p)?;
+                report.add("orphans", Fixed, format!("deleted {what} {}", p.display()));
+            } else {
+                report.add("orphans", Warn, format!("{what}: {}", p.display()));
+            }
+        }
+    }
+
+    // 4. executable bit
+    if !skipped("exec-bit") {
+        for m in load_manifests().into_iter().filter(|m| !m.no_exec) {
+            let script = dir.join(&m.name);
+            let Ok(md) = fs::metadata(&script) else { continue };
+            if md.permissions().mode() & 0o111 != 0 { continue; }
+            if fix {
+                let mut perm = md.permissions();
+                perm.set_mode(0o755);
+                fs::set_permissions(&script, perm).ctx("setting permissions on", &script)?;
+                report.add("exec-bit", Fixed, format!("restored executable bit on {}", script.display()));
+            } else {
+                report.add("exec-bit", Warn, format!("{} is not executable", script.display()));
+            }
+        }
+        report.clean("exec-bit", "every script is executable");
+    }
+
+    // 5. names/aliases two plugins (or a plugin and a built-in) both claim
+    if !skipped("collisions") {
+        for c in cli_collisions(&load_manifests()) {
+            let what = if c.word == c.plugin { "name".to_owned() } else { format!("alias `{}`", c.word) };
+            report.add("collisions", Warn, format!("plugin `{}`: {what} is already {}", c.plugin, c.taken_by));
+        }
+        report.clean("collisions", "no name or alias collisions");
+    }
+
+    // 6. `add --link` targets (nothing to fix automatically: the source is gone)
+    if !skipped("links") {
+        for (name, target) in broken_links()? {
+            report.add("links", Fail, format!("`{name}` links to missing {} (re-add or `uni remove {name}`)", target.display()));
+        }
+        report.clean("links", "no broken links");
+    }
+
+    // 7. scripts edited since install (report only: the edit may be deliberate)
+    if !skipped("modified") {
+        for m in modified_plugins() {
+            let script = plugin_dir().join(&m.name);
+            report.add("modified", Warn, format!("{} changed since it was installed (re-add it to accept the edit)", script.display()));
+        }
+        report.clean("modified", "no script changed since install");
+    }
+
+    // 8. sidecars that don't parse (report only: a hand edit needs a hand fix)
+    if !skipped("manifests") {
+        for line in broken_sidecars()? {
+            report.add("manifests", Fail, line);
+        }
+        report.clean("manifests", "every manifest parses");
+    }
+
+    // 9. network filesystem (report only: moving the dir is the user's call)
+    if !skipped("network-fs") {
+        if let Some(kind) = network_fs(&dir) {
+            report.add("network-fs", Warn, network_fs_warning(&dir, kind));
+        }
+        report.clean("network-fs", "plugin dir is on a local filesystem");
+    }
+
+    report.finish(if fix { "" } else { " (try `uni doctor --fix`)" })
+}
+
+/* ---------- selftest ---------- */
+
+// no plugin declares this, so every conforming plugin must reject it
+const SELFTEST_UNKNOWN: &str = "__uni_selftest_unknown__";
+
+/// Check `script` the way `uni add` and `build_cli` will use it: the
+/// manifest (from `--manifest`, or a static sidecar), `--commands` for
+/// dynamic plugins, each declared subcommand exiting 0, and an undeclared
+/// one exiting non-zero.  Subcommands really run, with stdin closed.
+fn selftest(script: &Path, timeout: Duration) -> Result<(), Box<dyn std::error::Error>> {
+    if !script.is_file() {
+        return Err(format!("{}: no such file", script.display()).into());
+    }
+    let mut failures = 0;
+    let mut check = |ok: bool, what: &str, detail: &str| {
+        if ok {
+            println!("✅ {what}");
+        } else {
+            println!("❌ {what}{}{detail}", if detail.is_empty() { "" } else { ": " });
+            failures += 1;
+        }
+    };
+    let skip = |what: &str, why: &str| println!("⏭️  {what} ({why})");
+    let run = |uv_args: &[String], args: &[&str]| output_with_timeout(uv_run(uv_args, script).args(args), timeout);
+    let stderr_tail = |out: &std::process::Output| {
+        String::from_utf8_lossy(&out.stderr).trim().lines().last().unwrap_or("").to_owned()
+    };
+
+    // 1. the manifest
+    let m = match companion_sidecar(script) {
+        Ok(Some(m)) => {
+            check(true, "static manifest (sidecar) parses", "");
+            m
+        }
+        Err(e) => {
+            check(false, "static manifest (sidecar) parses", &e.to_string());
+            return Err("1 check failed".into());
+        }
+        Ok(None) => {
+            let out = match run(&[], &["--manifest"]) {
+                Ok(out) => out,
+                Err(e) => {
+                    check(false, "`--manifest` runs", &e.to_string());
+                    return Err("1 check failed".into());
+                }
+            };
+            check(out.status.success(), "`--manifest` exits 0", &stderr_tail(&out));
+            match parse_plugin_manifest(&out.stdout) {
+                Ok(m) => { check(true, "`--manifest` prints a manifest", ""); m }
+                Err(e) => {
+                    check(false, "`--manifest` prints a manifest", &e);
+                    return Err(format!("{failures} check(s) failed").into());
+                }
+            }
+        }
+    };
+    let problems = manifest_problems(&m);
+    check(problems.is_empty(), "manifest passes `uni add`'s checks", &problems.join("; "));
+
+    // 2. dynamic subcommand discovery
+    let mut commands = m.commands.clone();
+    if m.dynamic_commands {
+        match run(&m.uv_args, &["--commands"]) {
+            Ok(out) => match serde_json::from_slice::<Vec<SubCmdMeta>>(&out.stdout) {
+                Ok(cmds) if out.status.success() => { check(true, "`--commands` lists subcommands", ""); commands = cmds; }
+                Ok(_) => check(false, "`--commands` lists subcommands", &stderr_tail(&out)),
+                Err(e) => check(false, "`--commands` lists subcommands", &format!("not a JSON list of subcommands: {e}")),
+            },
+            Err(e) => check(false, "`--commands` lists subcommands", &e.to_string()),
+        }
+    }
+
+    // 3. each declared subcommand, bare
+    for c in &commands {
+        let what = format!("`{}` exits 0", c.name);
+        if c.args.iter().flatten().any(|a| a.required) {
+            skip(&what, "has required arguments");
+            continue;
+        }
+        match run(&m.uv_args, &[&c.name]) {
+            Ok(out) => check(out.status.success(), &what, &stderr_tail(&out)),
+            Err(e) => check(false, &what, &e.to_string()),
+        }
+    }
+
+    // 4. an undeclared subcommand
+    let what = "an unknown subcommand exits non-zero";
+    if commands.is_empty() {
+        skip(what, "no subcommands declared: all arguments are the plugin's");
+    } else if m.allow_any_subcommand {
+        skip(what, "allow_any_subcommand");
+    } else {
+        match run(&m.uv_args, &[SELFTEST_UNKNOWN]) {
+            Ok(out) => check(!out.status.success(), what, &format!("`{SELFTEST_UNKNOWN}` exited 0")),
+            Err(e) => check(false, what, &e.to_string()),
+        }
+    }
+
+    if failures > 0 {
+        return Err(format!("{failures} check(s) failed").into());
+    }
+    say(format_args!("🎉 {} follows the plugin protocol", script.display()));
+    Ok(())
+}
+
+/* ---------- inspect ---------- */
+
+// top-level modules worth a second look in an untrusted script
+const PROCESS_MODULES: &[&str] = &["subprocess", "os", "pty", "shutil", "multiprocessing", "ctypes", "importlib"];
+const NETWORK_MODULES: &[&str] = &[
+    "socket", "ssl", "urllib", "http", "ftplib", "smtplib", "telnetlib", "xmlrpc",
+    "asyncio", "requests", "httpx", "aiohttp", "urllib3", "paramiko", "websockets",
+];
+
+/// The body of the `# /// script` … `# ///` block (PEP 723), `# ` stripped.
+fn pep723_block(src: &str) -> Option<String> {
+    let mut lines = src.lines().skip_while(|l| l.trim_end() != "# /// script");
+    lines.next()?;
+    let mut body = String::new();
+    for line in lines {
+        if line.trim_end() == "# ///" {
+            return Some(body);
+        }
+        let text = line.strip_prefix("# ").or_else(|| line.strip_prefix('#'))?;   // not a comment: malformed
+        body.push_str(text);
+        body.push('\n');
+    }
+    None
+}
+
+/// The quoted strings of `key = "…"` or `key = [ "…", … ]` in a TOML snippet;
+/// enough for the two keys PEP 723 defines, not a TOML parser.
+fn toml_strings(toml: &str, key: &str) -> Vec<String> {
+    let Some(start) = toml.lines().position(|l| l.split('=').next().is_some_and(|k| k.trim() == key)) else {
+        return Vec::new();
+    };
+    let mut value = String::new();
+    for line in toml.lines().skip(start) {
+        let line = if value.is_empty() { line.split_once('=').map_or("", |(_, v)| v) } else { line };
+        value.push_str(line);
+        value.push('\n');
+        if !value.trim_start().starts_with('[') || line.contains(']') {
+            break;
+        }
+    }
+    let mut out = Vec::new();
+    let mut rest = value.as_str();
+    while let Some(open) = rest.find(['"', '\'']) {
+        let quote = rest.as_bytes()[open] as char;
+        let Some(len) = rest[open + 1..].find(quote) else { break };
+        out.push(rest[open + 1..open + 1 + len].to_owned());
+        rest = &rest[open + 2 + len..];
+    }
+    out
+}
+
+/// Top-level module names from `import a, b.c as d` / `from a.b import c`
+/// lines, in first-seen order; relative imports are skipped.
+fn python_imports(src: &str) -> Vec<String> {
+    let mut seen = Vec::new();
+    for line in src.lines().map(str::trim_start) {
+        let names: Vec<&str> = if let Some(rest) = line.strip_prefix("import ") {
+            rest.split('#').next().unwrap_or("").split(',').collect()
+        } else if let Some(rest) = line.strip_prefix("from ") {
+            rest.split_whitespace().next().into_iter().collect()
+        } else {
+            continue;
+        };
+        for n in names {
+            let module = n.split_whitespace().next().unwrap_or("").split('.').next().unwrap_or("");
+            if module.is_empty() || !module.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                continue;
+            }
+            if !seen.iter().any(|s| s == module) {
+                seen.push(module.to_owned());
+            }
+        }
+    }
+    seen
+}
+
+/// `uni inspect`: read `script` as text and report what it declares and
+/// imports.  Nothing is executed; a non-static manifest is only mentioned.
+fn inspect_script(script: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let src = fs::read_to_string(script).ctx("reading", script)?;
+    println!("script:      {}", script.display());
+    if let Some(shebang) = src.lines().next().filter(|l| l.starts_with("#!")) {
+        println!("shebang:     {shebang}");
+    }
+
+    match pep723_block(&src) {
+        Some(block) => {
+            if let Some(py) = toml_strings(&block, "requires-python").first() {
+                println!("python:      {py}");
+            }
+            let deps = toml_strings(&block, "dependencies");
+            println!("depends on:  {}", if deps.is_empty() { "(nothing)".into() } else { deps.join(", ") });
+        }
+        None => println!("depends on:  no `# /// script` header (uv will install nothing)"),
+    }
+
+    let imports = python_imports(&src);
+    let flagged = |list: &[&str]| imports.iter().filter(|i| list.contains(&i.as_str())).cloned().collect::<Vec<_>>();
+    println!("imports:     {}", if imports.is_empty() { "(none)".into() } else { imports.join(", ") });
+    let (process, network) = (flagged(PROCESS_MODULES), flagged(NETWORK_MODULES));
+    if !process.is_empty() {
+        println!("⚠️  processes/filesystem: {}", process.join(", "));
+    }
+    if !network.is_empty() {
+        println!("⚠️  network: {}", network.join(", "));
+    }
+
+    match companion_sidecar(script)? {
+        Some(m) => {
+            println!("manifest:    {} v{} (static, {})", m.name, m.version, script.with_extension("json").display());
+            for c in &m.commands {
+                println!("  {:<12} {}", c.name, c.description);
+            }
+            for problem in manifest_problems(&m) {
+                println!("⚠️  {problem}");
+            }
+        }
+        None => println!("manifest:    printed by `--manifest` at add time (not run here)"),
+    }
+    Ok(())
 }
 
-#[derive(Subcommand)]
-enum BuiltIn {
-    Add    { path: PathBuf },
-    Remove { name: String },
-    List,
-    Create { name: String },  
-    Export { #[arg(default_value = "plugins.zip")] file: PathBuf },
-    Import { file: PathBuf },
-    EnsurePython {
-        #[arg(long)]
-        force: bool,
-    },
+/* ---------- stats ---------- */
+
+/// Bytes under `path`, symlinks not followed (a linked script counts as the
+/// link itself); 0 if it doesn't exist.
+fn disk_usage(path: &Path) -> u64 {
+    let Ok(md) = fs::symlink_metadata(path) else { return 0 };
+    if !md.is_dir() {
+        return md.len();
+    }
+    fs::read_dir(path).into_iter().flatten().flatten().map(|e| disk_usage(&e.path())).sum()
 }
 
-/* ---------- manifest ---------- */
+fn fmt_bytes(n: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+    if n < 1024 {
+        return format!("{n} B");
+    }
+    let mut v = n as f64 / 1024.0;
+    let mut unit = 0;
+    while v >= 1024.0 && unit + 1 < UNITS.len() {
+        v /= 1024.0;
+        unit += 1;
+    }
+    format!("{v:.1} {}", UNITS[unit])
+}
 
-#[derive(Serialize, Deserialize)]
-struct SubCmdMeta {
-    name: String,
-    description: String,
+fn stats(disk: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let plugins = load_manifests();
+    if !disk {
+        println!("plugins:     {}", plugins.len());
+        println!("subcommands: {}", plugins.iter().map(|m| m.commands.len()).sum::<usize>());
+        println!("pinned:      {}", plugins.iter().filter(|m| m.pinned).count());
+        println!("linked:      {}", plugins.iter().filter(|m| m.linked).count());
+        println!("plugin dir:  {} (per plugin: `uni stats --disk`)", fmt_bytes(disk_usage(&plugin_dir())));
+        return Ok(());
+    }
+
+    let dir = plugin_dir();
+    let mut rows: Vec<(String, [u64; 4])> = plugins
+        .into_iter()
+        .map(|m| {
+            let sizes = [
+                disk_usage(&dir.join(&m.name)),
+                disk_usage(&dir.join(format!("{}.json", m.name))),
+                disk_usage(&asset_dir(&m.name)),
+                disk_usage(&data_dir(&m.name)),
+            ];
+            (m.name, sizes)
+        })
+        .collect();
+    rows.sort_by(|(a, x), (b, y)| y.iter().sum::<u64>().cmp(&x.iter().sum()).then_with(|| a.cmp(b)));
+
+    // `du`-style: bytes, a tab, the name
+    if porcelain() {
+        for (name, sizes) in &rows {
+            println!("{}\t{name}", sizes.iter().sum::<u64>());
+        }
+        return Ok(());
+    }
+    println!("{:<20} {:>10} {:>10} {:>10} {:>10} {:>10}", "PLUGIN", "TOTAL", "SCRIPT", "MANIFEST", "ASSETS", "DATA");
+    for (name, [script, sidecar, assets, data]) in &rows {
+        println!(
+            "{name:<20} {:>10} {:>10} {:>10} {:>10} {:>10}",
+            fmt_bytes(script + sidecar + assets + data),
+            fmt_bytes(*script),
+            fmt_bytes(*sidecar),
+            fmt_bytes(*assets),
+            fmt_bytes(*data),
+        );
+    }
+    Ok(())
 }
 
-#[derive(Serialize, Deserialize)]
-struct Manifest {
-    name: String,
-    description: String,
-    version: String,
-    #[serde(default)]
-    commands: Vec<SubCmdMeta>,
+/* ---------- graph ---------- */
+
+/// `(plugin, dependency)` pairs whose dependency isn't installed.
+fn missing_dependencies(plugins: &[Manifest]) -> Vec<(String, String)> {
+    let names: std::collections::HashSet<&str> = plugins.iter().map(|m| m.name.as_str()).collect();
+    plugins
+        .iter()
+        .flat_map(|m| m.requires_plugins.iter().map(move |d| (m, d)))
+        .filter(|(_, d)| !names.contains(d.as_str()))
+        .map(|(m, d)| (m.name.clone(), d.clone()))
+        .collect()
 }
 
-/* ---------- plugin-directory helpers ---------- */
+/// Every dependency cycle once, rotated to start at its smallest name.
+fn dependency_cycles(plugins: &[Manifest]) -> Vec<Vec<String>> {
+    let by_name: std::collections::HashMap<&str, &Manifest> = plugins.iter().map(|m| (m.name.as_str(), m)).collect();
+    let mut cycles = std::collections::BTreeSet::new();
+    let mut done = std::collections::HashSet::new();
 
-fn plugin_dir() -> PathBuf {
-    ProjectDirs::from("", "", "mycli")
-        .expect("cannot determine config dir")
-        .config_dir()
-        .join("plugins")
+    fn walk<'a>(
+        name: &'a str,
+        by_name: &std::collections::HashMap<&'a str, &'a Manifest>,
+        stack: &mut Vec<&'a str>,
+        done: &mut std::collections::HashSet<&'a str>,
+        cycles: &mut std::collections::BTreeSet<Vec<String>>,
+    ) {
+        if let Some(at) = stack.iter().position(|s| *s == name) {
+            let mut cycle: Vec<String> = stack[at..].iter().map(|s| s.to_string()).collect();
+            let min = (0..cycle.len()).min_by_key(|&i| &cycle[i]).unwrap_or(0);
+            cycle.rotate_left(min);
+            cycles.insert(cycle);
+            return;
+        }
+        if done.contains(name) { return; }
+        let Some(m) = by_name.get(name) else { return };   // missing: reported separately
+        stack.push(name);
+        for d in &m.requires_plugins {
+            walk(d, by_name, stack, done, cycles);
+        }
+        stack.pop();
+        done.insert(name);
+    }
+
+    for m in plugins {
+        walk(&m.name, &by_name, &mut Vec::new(), &mut done, &mut cycles);
+    }
+    cycles.into_iter().collect()
 }
 
-fn ensure_plugin_dir() -> Result<(), IoError> {
-    fs::create_dir_all(plugin_dir())
+fn plugin_graph(dot: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut plugins = load_manifests();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    let missing = missing_dependencies(&plugins);
+    let cycles = dependency_cycles(&plugins);
+
+    if dot {
+        println!("digraph uni {{");
+        for m in &plugins {
+            println!("  {:?};", m.name);
+            for d in &m.requires_plugins {
+                println!("  {:?} -> {d:?};", m.name);
+            }
+        }
+        let mut absent: Vec<&str> = missing.iter().map(|(_, d)| d.as_str()).collect();
+        absent.sort();
+        absent.dedup();
+        for d in absent {
+            println!("  {d:?} [style=dashed, color=red];");
+        }
+        println!("}}");
+    } else {
+        // roots are plugins nothing else needs; a pure cycle has none, so
+        // anything not reached from a root is printed as a root afterwards
+        let needed: std::collections::HashSet<&str> =
+            plugins.iter().flat_map(|m| m.requires_plugins.iter().map(String::as_str)).collect();
+        let by_name: std::collections::HashMap<&str, &Manifest> = plugins.iter().map(|m| (m.name.as_str(), m)).collect();
+        let mut shown = std::collections::HashSet::new();
+
+        fn tree<'a>(
+            m: &'a Manifest,
+            prefix: &str,
+            by_name: &std::collections::HashMap<&str, &'a Manifest>,
+            path: &mut Vec<&'a str>,
+            shown: &mut std::collections::HashSet<&'a str>,
+        ) {
+            shown.insert(&m.name);
+            path.push(&m.name);
+            for (i, d) in m.requires_plugins.iter().enumerate() {
+                let last = i + 1 == m.requires_plugins.len();
+                let (branch, indent) = if last { ("└── ", "    ") } else { ("├── ", "│   ") };
+                match by_name.get(d.as_str()) {
+                    None => println!("{prefix}{branch}{d} (missing)"),
+                    Some(_) if path.contains(&d.as_str()) => println!("{prefix}{branch}{d} (cycle)"),
+                    Some(dm) => {
+                        println!("{prefix}{branch}{d}");
+                        tree(dm, &format!("{prefix}{indent}"), by_name, path, shown);
+                    }
+                }
+            }
+            path.pop();
+        }
+
+        let roots = plugins.iter().filter(|m| !needed.contains(m.name.as_str()));
+        for m in roots.collect::<Vec<_>>().into_iter().chain(&plugins) {
+            if shown.contains(m.name.as_str()) { continue; }
+            println!("{} {}", m.name, m.version);
+            tree(m, "", &by_name, &mut Vec::new(), &mut shown);
+        }
+    }
+
+    for (m, d) in &missing {
+        diag!("❌ `{m}` requires `{d}`, which is not installed");
+    }
+    for c in &cycles {
+        diag!("❌ dependency cycle: {} → {}", c.join(" → "), c[0]);
+    }
+    if !missing.is_empty() || !cycles.is_empty() {
+        return Err(format!("{} missing dependency(ies), {} cycle(s)", missing.len(), cycles.len()).into());
+    }
+    Ok(())
 }
 
-/* ---------- add / remove / list ---------- */
+/* ---------- validate-all ---------- */
 
-fn validate_and_copy(path: &Path) -> Result<Manifest, Box<dyn std::error::Error>> {
-    // Run script with --manifest and parse JSON
-    let out = Cmd::new("uv")      // interpreter call avoids chmod issues
-        .arg("run")
-        .arg(path)
-        .arg("--manifest")
-        .output()?;
-    if !out.status.success() {
-        eprintln!(
-            "plugin did not return valid manifest (exit {}):\n{}",
-            out.status,
-            String::from_utf8_lossy(&out.stderr)
-        );
+enum Revalidation {
+    // with the version the manifest now reports
+    Ok(String),
+    Failed(String),
+    // nothing to re-run: the manifest didn't come from the script
+    Skipped(&'static str),
+}
+
+fn revalidate(m: &Manifest) -> Revalidation {
+    match m.manifest_origin {
+        ManifestOrigin::None => return Revalidation::Skipped("registered without a manifest"),
+        // the source's `.json` may be long gone; the stored copy is what runs
+        ManifestOrigin::Sidecar => {
+            return match check_manifest(m) {
+                Ok(()) => Revalidation::Ok(m.version.clone()),
+                Err(e) => Revalidation::Failed(e.to_string()),
+            };
+        }
+        ManifestOrigin::Exec => {}
+    }
+    // straight to `--manifest`: the installed `<name>.json` would pass for a companion sidecar
+    let opts = InstallOpts { uv_args: m.uv_args.clone(), ..Default::default() };
+    match fetch_manifest(&plugin_dir().join(&m.name), &opts) {
+        Ok(fresh) => match check_manifest(&fresh) {
+            Ok(()) => Revalidation::Ok(fresh.version),
+            Err(e) => Revalidation::Failed(e.to_string()),
+        },
+        Err(e) => Revalidation::Failed(e.to_string()),
     }
-    let manifest: Manifest = serde_json::from_slice(&out.stdout)?;
+}
 
-    // Copy script
-    let dest_script = plugin_dir().join(&manifest.name);
-    fs::copy(path, &dest_script)?;
-    let mut perm = fs::metadata(&dest_script)?.permissions();
-    perm.set_mode(0o755);
-    fs::set_permissions(&dest_script, perm)?;
+fn validate_all(parallel: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let mut plugins = load_manifests();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
 
-    // Save manifest JSON
-    let dest_meta = plugin_dir().join(format!("{}.json", manifest.name));
-    fs::write(dest_meta, serde_json::to_vec_pretty(&manifest)?)?;
+    let threads = if parallel { std::thread::available_parallelism().map_or(1, |n| n.get()).min(8) } else { 1 };
+    let results: Vec<Revalidation> = if threads == 1 || plugins.len() < 2 {
+        plugins.iter().map(revalidate).collect()
+    } else {
+        let chunk = plugins.len().div_ceil(threads);
+        std::thread::scope(|s| {
+            let handles: Vec<_> = plugins
+                .chunks(chunk)
+                .map(|part| s.spawn(|| part.iter().map(revalidate).collect::<Vec<_>>()))
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|h| h.join().unwrap_or_else(|_| vec![Revalidation::Failed("validator panicked".into())]))
+                .collect()
+        })
+    };
 
-    Ok(manifest)
+    let (mut ok, mut failed, mut skipped) = (0, 0, 0);
+    for (m, r) in plugins.iter().zip(results) {
+        match r {
+            Revalidation::Ok(version) => {
+                ok += 1;
+                // still valid, but not what was installed: worth a re-add
+                let note = if version != m.version { format!(" (script now says v{version})") } else { String::new() };
+                say(format_args!("✅ {} v{}{note}", m.name, m.version));
+            }
+            Revalidation::Failed(e) => {
+                failed += 1;
+                println!("❌ {}: {e}", m.name);
+            }
+            Revalidation::Skipped(why) => {
+                skipped += 1;
+                say(format_args!("⏭️  {}: {why}", m.name));
+            }
+        }
+    }
+    say(format_args!("{ok} valid, {failed} failed, {skipped} skipped"));
+    if failed > 0 {
+        return Err(format!("{failed} plugin(s) failed validation").into());
+    }
+    Ok(())
 }
 
-fn remove_plugin(name: &str) -> Result<(), IoError> {
-    let dir = plugin_dir();
-    let script = dir.join(name);
-    let meta   = dir.join(format!("{}.json", name));
-    if script.exists() { fs::remove_file(script)?; }
-    if meta.exists()   { fs::remove_file(meta)?;   }
+/* ---------- freeze ---------- */
+
+fn freeze_plugins() -> Result<(), Box<dyn std::error::Error>> {
+    let mut plugins: Vec<LockEntry> = load_manifests()
+        .into_iter()
+        .map(|m| LockEntry { name: m.name, version: m.version, source: m.source })
+        .collect();
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));   // stable output for diffs
+
+    for p in plugins.iter().filter(|p| p.source.is_none()) {
+        diag!("⚠️  {} has no recorded source; `uni sync` cannot reinstall it", p.name);
+    }
+    println!("{}", serde_json::to_string_pretty(&Lockfile { plugins })?);
     Ok(())
 }
 
-fn list_plugins() -> Result<(), IoError> {
-    for entry in fs::read_dir(plugin_dir())? {
-        let p = entry?.path();
-        if p.extension().and_then(|e| e.to_str()) == Some("json") {
-            let data = fs::read(&p)?;
-            let m: Manifest = serde_json::from_slice(&data)?;
-            println!("- {}  (v{})  {}", m.name, m.version, m.description);
+/* ---------- sync ---------- */
+
+enum SyncStep<'a> {
+    Install(&'a LockEntry),
+    Update { want: &'a LockEntry, have: String },
+    Remove(String),
+}
+
+fn sync_plugins(lockfile: &Path, prune: bool, dry_run: bool, force: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let lock: Lockfile = serde_json::from_slice(&fs::read(lockfile).ctx("reading", lockfile)?)?;
+    let installed = load_manifests();
+
+    let mut plan = Vec::new();
+    for want in &lock.plugins {
+        match installed.iter().find(|m| m.name == want.name) {
+            None => plan.push(SyncStep::Install(want)),
+            Some(m) if m.pinned && !force => {
+                if m.version != want.version {
+                    println!("📌 {} pinned at v{}, not syncing to v{}", m.name, m.version, want.version);
+                }
+            }
+            Some(m) if m.version != want.version => {
+                plan.push(SyncStep::Update { want, have: m.version.clone() })
+            }
+            Some(_) => {}
+        }
+    }
+    if prune {
+        for m in installed.iter().filter(|m| force || !m.pinned) {
+            if !lock.plugins.iter().any(|p| p.name == m.name) {
+                plan.push(SyncStep::Remove(m.name.clone()));
+            }
+        }
+    }
+
+    if plan.is_empty() {
+        say(format_args!("✅ Nothing to sync from {}", lockfile.display()));
+        return Ok(());
+    }
+    // under --porcelain the plan is replaced by the paths actually touched
+    for step in &plan {
+        match step {
+            SyncStep::Install(w)         => say(format_args!("+ {} v{}", w.name, w.version)),
+            SyncStep::Update { want, have } => say(format_args!("~ {} v{have} → v{}", want.name, want.version)),
+            SyncStep::Remove(name)       => say(format_args!("- {name}")),
+        }
+    }
+    if dry_run { return Ok(()); }
+
+    // the lockfile is the whole set, so a dependency may just come later in it
+    let opts = InstallOpts { provided: lock.plugins.iter().map(|p| p.name.clone()).collect(), ..Default::default() };
+    let mut failed = 0;
+    for step in &plan {
+        let want = match step {
+            SyncStep::Remove(name) => {
+                remove_plugin(name, false)?;
+                if porcelain() { println!("{}", plugin_dir().join(name).display()); }
+                continue;
+            }
+            SyncStep::Install(w) | SyncStep::Update { want: w, .. } => w,
+        };
+        let Some(src) = &want.source else {
+            diag!("⚠️  {}: no source recorded in lockfile, skipped", want.name);
+            failed += 1;
+            continue;
+        };
+        match install_from_source(src, &opts) {
+            Ok(m) => {
+                if m.version != want.version {
+                    diag!("⚠️  {}: source now provides v{}, lockfile wants v{}", m.name, m.version, want.version);
+                }
+                if porcelain() { println!("{}", plugin_dir().join(&m.name).display()); }
+            }
+            Err(e) => { diag!("❌ {}: {e}", want.name); failed += 1; }
         }
     }
+    if failed > 0 {
+        return Err(format!("{failed} plugin(s) could not be synced").into());
+    }
     Ok(())
 }
 
 /* ---------- dynamic CLI assembly ---------- */
 
 fn load_manifests() -> Vec<Manifest> {
-    let mut out = Vec::new();
-    if let Ok(rd) = fs::read_dir(plugin_dir()) {
-        for entry in rd.flatten() {
-            let p = entry.path();
-            if p.extension().and_then(|e| e.to_str()) == Some("json") {
-                if let Ok(bytes) = fs::read(&p) {
-                    if let Ok(m) = serde_json::from_slice::<Manifest>(&bytes) { out.push(m); }
+    let dir = plugin_dir();
+    let Ok(rd) = fs::read_dir(&dir) else { return Vec::new() };
+    let mut sidecars: Vec<PathBuf> = rd
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().and_then(|e| e.to_str()) == Some("json"))
+        .collect();
+    sidecars.sort();                     // same order on every run
+
+    let load = |p: &PathBuf| -> Option<Manifest> {
+        let m: Manifest = match serde_json::from_slice(&fs::read(p).ok()?) {
+            Ok(m) => m,
+            Err(e) => {
+                if verbose() {
+                    diag!("⚠️  skipping {}", sidecar_error(p, &e));
                 }
+                return None;
+            }
+        };
+        // a sidecar whose script is gone would only fail at spawn time
+        if !dir.join(&m.name).is_file() {
+            if verbose() {
+                diag!("⚠️  skipping `{}`: script missing from {}", m.name, dir.display());
             }
+            return None;
+        }
+        Some(m)
+    };
+
+    sidecars.iter().filter_map(load).collect()
+}
+
+/* ---------- dynamic subcommand discovery ---------- */
+
+const COMMANDS_CACHE_TTL: Duration = Duration::from_secs(300);
+const COMMANDS_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Subcommands for a `dynamic_commands` plugin, from a short-lived cache or a
+/// fresh `--commands` call.  Falls back to the static list on any failure so a
+/// broken plugin can't take the whole CLI down with it.
+fn dynamic_commands(m: &Manifest) -> Vec<SubCmdMeta> {
+    let script = plugin_dir().join(&m.name);
+    let cache = cache_dir().join("commands").join(format!("{}.json", m.name));
+
+    let mtime = |p: &Path| fs::metadata(p).and_then(|md| md.modified()).ok();
+    let fresh = match (mtime(&cache), mtime(&script)) {
+        (Some(c), Some(s)) => c >= s && c.elapsed().is_ok_and(|age| age < COMMANDS_CACHE_TTL),
+        _ => false,
+    };
+    if fresh
+        && let Ok(bytes) = fs::read(&cache)
+        && let Ok(cmds) = serde_json::from_slice(&bytes)
+    {
+        return cmds;
+    }
+
+    let mut cmd = uv_run(&m.uv_args, &script);
+    cmd.arg("--commands");
+    let cmds = output_with_timeout(&mut cmd, COMMANDS_TIMEOUT)
+        .ok()
+        .filter(|out| out.status.success())
+        .and_then(|out| serde_json::from_slice::<Vec<SubCmdMeta>>(&out.stdout).ok());
+    match cmds {
+        Some(cmds) => {
+            // cache is best-effort; a read-only cache dir just means no caching
+            let _ = fs::create_dir_all(cache.parent().unwrap());
+            let _ = fs::write(&cache, serde_json::to_vec(&cmds).unwrap_or_default());
+            cmds
         }
+        None => m.commands.clone(),
     }
-    out
 }
 
 /* ---------- create CLI command template ---------- */
 
 
-fn create_template(name: &str) -> std::io::Result<PathBuf> {
-    let file_name = format!("{name}.py");
+fn create_template(name: &str, static_manifest: bool, with_tests: bool) -> std::io::Result<PathBuf> {
+    let file_name = format!("{}.py", file_safe(name));
     let path      = std::env::current_dir()?.join(&file_name);
 
+    // No `--manifest` contract: `uni add` picks up the `<name>.json` next to it
+    const STATIC_TEMPLATE: &str = r#"#!/usr/bin/env -S uv run --script
+# /// script
+# requires-python = ">=3.8"
+# dependencies = []
+# ///
+import sys
+
+def main(args):
+    print("<<NAME>> called with", args)
+
+if __name__ == "__main__":
+    main(sys.argv[1:])
+"#;
+
     // Simple one-shot write; will overwrite if the file exists
     const TEMPLATE: &str = r#"#!/usr/bin/env -S uv run --script
 # /// script
@@ -148,7 +4394,7 @@ fn create_template(name: &str) -> std::io::Result<PathBuf> {
 #     ///add you dependencies here
 # ]
 # ///
-import sys, json, subprocess
+import sys, json, shlex, subprocess
 
 MANIFEST = {
     "name": "<<NAME>>",
@@ -157,14 +4403,24 @@ MANIFEST = {
     "commands": [
         { "name": "run",    "description": "Run the job" },
         { "name": "status", "description": "Show status" }
-    ]
+    ],
+    "examples": ["uni <<NAME>> run", "uni <<NAME>> status"]
 }
 
 def run_cmd(cmd: list[str]) -> None:
-    """Run a shell command and stream its output; abort on failure."""
+    """Run a command given as a list, e.g. ["git", "tag", name]; stream its
+    output and exit with its code on failure.  No shell is involved, so each
+    item reaches the program as-is: the safe way to pass user input."""
     result = subprocess.run(cmd, check=False, text=True)
     if result.returncode != 0:
-        sys.exit(result.returncode);
+        sys.exit(result.returncode)
+
+def run_shell(cmd_str: str) -> None:
+    """run_cmd for a command written as one string, e.g. "git log -n 5".
+    It is split shell-style with shlex but never given to a shell, so `;`,
+    `|` and `$(...)` are plain text.  Values formatted into the string can
+    still split into extra arguments: pass those through run_cmd instead."""
+    run_cmd(shlex.split(cmd_str))
 
 def manifest():
     print(json.dumps(MANIFEST))
@@ -182,7 +4438,9 @@ def main():
     if sub in cmds:
         cmds[sub](sys.argv[2:])
     else:
-        print("usage: {0} {{run|status}} …".format(MANIFEST["name"]))
+        # non-zero, so `uni selftest` (and scripts) can tell a typo from success
+        print("usage: {0} {{run|status}} …".format(MANIFEST["name"]), file=sys.stderr)
+        sys.exit(2)
 
 if __name__ == "__main__":
     if "--manifest" in sys.argv:
@@ -191,68 +4449,510 @@ if __name__ == "__main__":
         main()
 "#;
 
-    let contents = TEMPLATE.replace("<<NAME>>", name);
-    std::fs::write(&path, contents)?;            /* std::fs::write does the create/truncate in one step :contentReference[oaicite:4]{index=4} */
+    let template = if static_manifest { STATIC_TEMPLATE } else { TEMPLATE };
+    let contents = template.replace("<<NAME>>", &py_str_escape(name));
+    let m = Manifest {
+        name: name.to_owned(),
+        description: "Describe what this plugin does".into(),
+        version: "0.1.0".into(),
+        examples: vec![format!("uni {name} hello world")],
+        ..Default::default()
+    };
+    for problem in manifest_problems(&m) {
+        diag!("⚠️  {problem}; `uni add` will refuse it until the manifest is fixed");
+    }
+    if static_manifest {
+        let json = path.with_extension("json");
+        std::fs::write(&json, serde_json::to_vec_pretty(&m)?).ctx("writing", &json)?;
+    }
+    std::fs::write(&path, contents).ctx("writing", &path)?;            /* std::fs::write does the create/truncate in one step :contentReference[oaicite:4]{index=4} */
 
     // Make it executable on Unix; ignored on Windows
     #[cfg(unix)]
     {
         use std::os::unix::fs::PermissionsExt;
-        let mut p = std::fs::metadata(&path)?.permissions();
+        let mut p = std::fs::metadata(&path).ctx("reading", &path)?.permissions();
         p.set_mode(0o755);
-        std::fs::set_permissions(&path, p)?;
+        std::fs::set_permissions(&path, p).ctx("setting permissions on", &path)?;
+    }
+
+    if with_tests {
+        // subcommands TEMPLATE declares; the static one declares none
+        let commands: &[&str] = if static_manifest { &[] } else { &["run", "status"] };
+        let tests = test_template(name, static_manifest, commands)
+            .replace("<<FILE>>", &py_str_escape(&file_name))
+            .replace("<<UV>>", &py_str_escape(&resolve_uv_bin().value.to_string_lossy()));
+        let test = test_path(&path);
+        std::fs::write(&test, tests).ctx("writing", &test)?;
     }
 
     Ok(path)
 }
 
+fn test_path(script: &Path) -> PathBuf {
+    let stem = script.file_stem().unwrap_or_default().to_string_lossy();
+    script.with_file_name(format!("test_{stem}.py"))
+}
+
+/// pytest module for `create --with-tests`: the manifest contract plus one
+/// stub per declared subcommand.
+fn test_template(name: &str, static_manifest: bool, commands: &[&str]) -> String {
+    const HEADER: &str = r#"import json
+import os
+import pathlib
+import subprocess
+
+SCRIPT = pathlib.Path(__file__).with_name("<<FILE>>")
+UV = os.environ.get("UNI_UV_BIN") or "<<UV>>"
+
+
+def run_plugin(*args):
+    return subprocess.run([UV, "run", str(SCRIPT), *args], capture_output=True, text=True)
+
+
+"#;
+    const MANIFEST_TEST: &str = r#"def test_manifest_is_valid_json():
+    out = run_plugin("--manifest")
+    assert out.returncode == 0, out.stderr
+    manifest = json.loads(out.stdout)
+    assert manifest["name"] == "<<NAME>>"
+    assert manifest["version"]
+    assert isinstance(manifest["description"], str)
+"#;
+    // `--static` plugins have no `--manifest`; `uni add` reads the sidecar
+    const SIDECAR_TEST: &str = r#"def test_manifest_is_valid_json():
+    manifest = json.loads(SCRIPT.with_suffix(".json").read_text())
+    assert manifest["name"] == "<<NAME>>"
+    assert manifest["version"]
+    assert isinstance(manifest["description"], str)
+"#;
+    const SUBCOMMAND_TEST: &str = r#"
+
+def test_<<IDENT>>():
+    out = run_plugin("<<CMD>>")
+    assert out.returncode == 0, out.stderr
+    # TODO: assert on out.stdout
+"#;
+
+    let mut text = String::from(HEADER);
+    text.push_str(if static_manifest { SIDECAR_TEST } else { MANIFEST_TEST });
+    for cmd in commands {
+        let ident: String = cmd.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+        text.push_str(&SUBCOMMAND_TEST.replace("<<IDENT>>", &ident).replace("<<CMD>>", cmd));
+    }
+    text.replace("<<NAME>>", &py_str_escape(name))
+}
+
+// every `<<NAME>>` in the templates sits inside a "…" literal, and JSON string
+// escapes are valid Python ones
+fn py_str_escape(s: &str) -> String {
+    let quoted = serde_json::to_string(s).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_owned()
+}
+
+// the name as a file stem: path separators, quotes, etc. become `_`
+fn file_safe(name: &str) -> String {
+    let safe: String = name
+        .chars()
+        .map(|c| if c.is_alphanumeric() || "-_.".contains(c) { c } else { '_' })
+        .collect();
+    match safe.trim_start_matches('.') {
+        "" => "plugin".into(),
+        s => s.to_owned(),
+    }
+}
+
 /* ---------- export CLI plugin commands ---------- */
 
 
-fn export_plugins(zip_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+/// Resolve the archive path and format: CLI args win, then config, then `plugins.zip`.
+fn export_target(file: Option<&PathBuf>, format: Option<ArchiveFormat>) -> (PathBuf, ArchiveFormat) {
+    let cfg = config();
+    let file = file.or(cfg.export_file.as_ref());
+    let format = format
+        .or_else(|| file.and_then(|f| ArchiveFormat::from_path(f)))
+        .unwrap_or(resolve_export_format().value);
+    let file = file.cloned().unwrap_or_else(|| PathBuf::from(format!("plugins.{}", format.extension())));
+    (file, format)
+}
+
+fn export_plugins(zip_path: &Path, format: ArchiveFormat, manifest_only: bool, exclude: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let files = export_selection(manifest_only, exclude)?;
+    write_archive(zip_path, format, &files)?;
+    report(zip_path, format_args!("📦  Exported plugins to {}", zip_path.display()));
+    Ok(())
+}
+
+/// `export --to-dir`: the files an archive would hold, copied as they are
+/// (mode bits included) so `uni import <dir>` can take them back.
+fn export_to_dir(out_dir: &Path, manifest_only: bool, exclude: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let files = export_selection(manifest_only, exclude)?;
+    fs::create_dir_all(out_dir).ctx("creating", out_dir)?;
+    for p in &files {
+        let dest = out_dir.join(p.file_name().unwrap());
+        fs::copy(p, &dest).ctx("copying", p)?;
+    }
+    report(out_dir, format_args!("📁  Copied {} file(s) to {}", files.len(), out_dir.display()));
+    Ok(())
+}
+
+/// `export_files`, minus `--exclude`d plugins and, for `--manifest-only`, scripts.
+fn export_selection(manifest_only: bool, exclude: &[String]) -> Result<Vec<PathBuf>, IoError> {
+    let mut files = export_files()?;
+    // script and sidecar go together: both are judged by the plugin name
+    files.retain(|p| {
+        let file = p.file_name().unwrap().to_string_lossy();
+        let name = file.strip_suffix(".json").unwrap_or(&file);
+        !exclude.iter().any(|pat| glob_match(pat, name))
+    });
+    if manifest_only {
+        files.retain(|p| p.extension().is_some_and(|e| e == "json"));
+    }
+    Ok(files)
+}
+
+/// `export --split`: one `<name>.<ext>` per installed plugin in `out_dir`,
+/// holding just its script and sidecar.
+fn export_split(out_dir: &Path, format: ArchiveFormat, exclude: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    fs::create_dir_all(out_dir).ctx("creating", out_dir)?;
+    let dir = plugin_dir();
+    let mut plugins = load_manifests();
+    plugins.retain(|m| !exclude.iter().any(|pat| glob_match(pat, &m.name)));
+    plugins.sort_by(|a, b| a.name.cmp(&b.name));
+    for m in &plugins {
+        let archive = out_dir.join(format!("{}.{}", m.name, format.extension()));
+        write_archive(&archive, format, &[dir.join(&m.name), dir.join(format!("{}.json", m.name))])?;
+        report(&archive, format_args!("📦  Exported {} to {}", m.name, archive.display()));
+    }
+    if plugins.is_empty() {
+        say("no plugins to export");
+    }
+    Ok(())
+}
+
+/// Shell-style match of the whole of `text`: `*` is any run, `?` any one char.
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let (p, t): (Vec<char>, Vec<char>) = (pattern.chars().collect(), text.chars().collect());
+    let (mut pi, mut ti) = (0, 0);
+    let mut star = None;                              // (pattern pos after `*`, text pos it matched up to)
+    while ti < t.len() {
+        match p.get(pi) {
+            Some('*') => { star = Some((pi + 1, ti)); pi += 1; }
+            Some(&c) if c == '?' || c == t[ti] => { pi += 1; ti += 1; }
+            _ => match star {
+                // let the last `*` swallow one more char and retry
+                Some((sp, st)) => { pi = sp; ti = st + 1; star = Some((sp, st + 1)); }
+                None => return false,
+            },
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
+
+/// Pack plugin-dir `files` (stored by file name only) into `zip_path`.
+fn write_archive(zip_path: &Path, format: ArchiveFormat, files: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    if format == ArchiveFormat::TarGz {
+        return export_tar_gz(zip_path, files);
+    }
+
     use zip::write::FileOptions;
     use std::io::Write;
 
-    let file = std::fs::File::create(zip_path)?;        // std::fs::File::create :contentReference[oaicite:2]{index=2}
+    let file = std::fs::File::create(zip_path).ctx("creating", zip_path)?;        // std::fs::File::create :contentReference[oaicite:2]{index=2}
     let mut zip = zip::ZipWriter::new(file);            // ZipWriter API :contentReference[oaicite:3]{index=3}
-    let opts = FileOptions::default().unix_permissions(0o644);
-
-    for entry in std::fs::read_dir(plugin_dir())? {     // read_dir iteration :contentReference[oaicite:4]{index=4}
-        let p = entry?.path();
-        if p.is_file() {
-            let name = p.file_name().unwrap().to_string_lossy();
-            zip.start_file(name, opts)?;                // each .py / .json becomes one entry
-            let data = std::fs::read(&p)?;
-            zip.write_all(&data)?;
-        }
+    for (i, p) in files.iter().enumerate() {
+        let name = p.file_name().unwrap().to_string_lossy();
+        progress("export", "writing", serde_json::json!({ "file": name, "index": i + 1, "total": files.len() }));
+        zip.start_file(name, FileOptions::default().unix_permissions(archive_mode(p)?))?;  // each .py / .json becomes one entry
+        let data = std::fs::read(p).ctx("reading", p)?;
+        zip.write_all(&data)?;
     }
     zip.finish()?;                                     // flush central directory
-    println!("📦  Exported plugins to {}", zip_path.display());
+    progress("export", "done", serde_json::json!({ "archive": zip_path, "count": files.len() }));
+    Ok(())
+}
+
+/// Mode of `p`'s archive entry: scripts keep theirs (0o755, or 0o644 for
+/// no_exec) so extracting the archive is enough to run them; sidecars are
+/// always 0o644.
+fn archive_mode(p: &Path) -> Result<u32, IoError> {
+    if p.extension().is_some_and(|e| e == "json") {
+        return Ok(0o644);
+    }
+    Ok(fs::metadata(p).ctx("reading", p)?.permissions().mode() & 0o777)
+}
+
+/// The plugin-dir files that go into an archive, in a stable order.
+fn export_files() -> Result<Vec<PathBuf>, IoError> {
+    let mut files = Vec::new();
+    let dir = plugin_dir();
+    for entry in fs::read_dir(&dir).ctx("listing", &dir)? {          // read_dir iteration :contentReference[oaicite:4]{index=4}
+        let p = entry.ctx("listing", &dir)?.path();
+        if p.is_file() { files.push(p); }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn export_tar_gz(tar_path: &Path, files: &[PathBuf]) -> Result<(), Box<dyn std::error::Error>> {
+    let file = fs::File::create(tar_path).ctx("creating", tar_path)?;
+    let mut tar = tar::Builder::new(flate2::write::GzEncoder::new(file, flate2::Compression::default()));
+    for (i, p) in files.iter().enumerate() {
+        let name = p.file_name().unwrap().to_string_lossy();
+        progress("export", "writing", serde_json::json!({ "file": name, "index": i + 1, "total": files.len() }));
+        let data = fs::read(p).ctx("reading", p)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(archive_mode(p)?);
+        header.set_mtime(fs::metadata(p).ctx("reading", p)?.modified()?.duration_since(UNIX_EPOCH)?.as_secs());
+        header.set_cksum();
+        tar.append_data(&mut header, name.as_ref(), data.as_slice())?;
+    }
+    tar.into_inner()?.finish()?;
+    progress("export", "done", serde_json::json!({ "archive": tar_path, "count": files.len() }));
+    Ok(())
+}
+
+/* ---------- import CLI plugin commands ---------- */
+
+// what importing does with a plugin that is already installed
+#[derive(Clone, Copy, PartialEq, clap::ValueEnum)]
+enum OverwritePolicy {
+    Skip,
+    Replace,
+    Upgrade,
+    // keep both: the incoming plugin gets a free `<name>-N`
+    Rename,
+}
+
+
+// a script to import: already extracted, or still inside the zip (`--in-memory`)
+#[derive(Clone)]
+enum ImportEntry {
+    File(PathBuf),
+    Zip { index: usize, name: String },
+}
+
+/// Unpack a tar.gz archive into `dest`.  Only plain files named at the top
+/// level are taken, as `export` writes them: a link, a directory, `../x` or
+/// `sub/x` fails the whole import before anything is installed.
+fn extract_tar_gz(tar_path: &Path, dest: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let file = fs::File::open(tar_path).ctx("opening", tar_path)?;
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(file));
+    for entry in tar.entries()? {
+        let mut entry = entry?;
+        let path = entry.path()?.into_owned();
+        let mut parts = path.components();
+        let name = match (parts.next(), parts.next()) {
+            (Some(std::path::Component::Normal(name)), None) if entry.header().entry_type().is_file() => name.to_owned(),
+            _ => return Err(format!("{}: refusing `{}`: not a plain file at the top level", tar_path.display(), path.display()).into()),
+        };
+        let out = dest.join(&name);
+        entry.unpack(&out).ctx("extracting", &out)?;
+    }
     Ok(())
 }
 
-/* ---------- import CLI plugin commands ---------- */
-
-
-fn import_plugins(zip_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    let file = std::fs::File::open(zip_path)?;
-    let mut archive = zip::read::ZipArchive::new(file)?;               // :contentReference[oaicite:0]{index=0}
+/// The sidecar `export` archived next to `entry` (`<name>.json` beside
+/// `<name>`), if any.  It is uni's own record, so it is taken whole.
+fn archived_sidecar<R: std::io::Read + std::io::Seek>(entry: &ImportEntry, archive: Option<&mut zip::ZipArchive<R>>) -> Option<Manifest> {
+    let bytes = match (entry, archive) {
+        (ImportEntry::File(p), _) => fs::read(p.with_file_name(format!("{}.json", p.file_name().unwrap().to_string_lossy()))).ok(),
+        (ImportEntry::Zip { name, .. }, Some(archive)) => archive.by_name(&format!("{name}.json")).ok().and_then(|mut f| {
+            let mut buf = Vec::new();
+            std::io::Read::read_to_end(&mut f, &mut buf).ok().map(|_| buf)
+        }),
+        (ImportEntry::Zip { .. }, None) => None,
+    };
+    bytes.and_then(|b| parse_manifest(&b).ok())
+}
+
+/// Install options that carry an archived sidecar's own fields (pin, source,
+/// `--set` values, a `--description` override, how the script is stored)
+/// over to the imported copy.
+fn archived_opts(m: &Manifest) -> InstallOpts {
+    InstallOpts {
+        source: m.source.clone(),
+        pin: m.pinned,
+        no_exec: m.no_exec,
+        executable: m.executable,
+        description: m.custom_description.then(|| m.description.clone()),
+        extra: m.extra.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        ..InstallOpts::default()
+    }
+}
+
+/// Indices of `names` reordered so that each comes after whatever it
+/// requires from the same batch; otherwise (and within a cycle) in order.
+fn dependency_order(names: &[String], requires: &[Vec<String>]) -> Vec<usize> {
+    fn visit(i: usize, names: &[String], requires: &[Vec<String>], state: &mut [u8], out: &mut Vec<usize>) {
+        if state[i] != 0 { return; }                  // 1 = on the stack (a cycle), 2 = placed
+        state[i] = 1;
+        for d in &requires[i] {
+            if let Some(j) = names.iter().position(|n| n == d) {
+                visit(j, names, requires, state, out);
+            }
+        }
+        state[i] = 2;
+        out.push(i);
+    }
+    let mut state = vec![0; names.len()];
+    let mut out = Vec::with_capacity(names.len());
+    for i in 0..names.len() {
+        visit(i, names, requires, &mut state, &mut out);
+    }
+    out
+}
+
+/// Top-level, non-JSON file entries of `archive`, in name order.
+fn zip_script_entries<R: std::io::Read + std::io::Seek>(archive: &mut zip::ZipArchive<R>) -> Result<Vec<(usize, String)>, Box<dyn std::error::Error>> {
+    let mut entries = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index)?;
+        let Some(path) = entry.enclosed_name() else { continue };     // `../` and absolute names
+        if !entry.is_file() || path.components().count() != 1 || path.extension().is_some_and(|e| e == "json") {
+            continue;
+        }
+        entries.push((index, path.to_string_lossy().into_owned()));
+    }
+    entries.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(entries)
+}
+
+fn import_plugins(zip_path: &Path, policy: OverwritePolicy, in_memory: bool, no_validate: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let is_tar = ArchiveFormat::from_path(zip_path) == Some(ArchiveFormat::TarGz);
+    let is_dir = zip_path.is_dir();                                    // `export --to-dir` output
+    if in_memory && (is_tar || is_dir) {
+        return Err("--in-memory needs a zip archive".into());
+    }
 
     // 1) unpack everything into an auto-cleaning temp dir
     let tmp = tempfile::tempdir()?;                                    // :contentReference[oaicite:1]{index=1}
-    archive.extract(&tmp)?;                                            // single call does the loop for us :contentReference[oaicite:2]{index=2}
+    let mut archive = None;
+    progress("import", "extracting", serde_json::json!({ "archive": zip_path }));
+    let unpacked = if is_dir { zip_path } else { tmp.path() };
+    if in_memory {
+        archive = Some(zip::read::ZipArchive::new(std::fs::File::open(zip_path).ctx("opening", zip_path)?)?);
+    } else if is_tar {
+        extract_tar_gz(zip_path, tmp.path())?;
+    } else if !is_dir {
+        let file = std::fs::File::open(zip_path).ctx("opening", zip_path)?;
+        let mut archive = zip::read::ZipArchive::new(file)?;           // :contentReference[oaicite:0]{index=0}
+        archive.extract(&tmp)?;                                        // single call does the loop for us :contentReference[oaicite:2]{index=2}
+    }
 
     // 2) walk the temp dir and feed every NON-JSON file to the validator
-    for entry in std::fs::read_dir(&tmp)? {                            // :contentReference[oaicite:3]{index=3}
-        let p = entry?.path();
-        if p.extension().and_then(|e| e.to_str()) == Some("json") {    // skip manifests
-            continue;
+    let opts = InstallOpts::default();
+    let mut timings = Vec::new();
+    let (mut added, mut replaced, mut upgraded, mut skipped, mut failed) = (0, 0, 0, 0, 0);
+    let mut scripts = Vec::new();
+    if let Some(archive) = archive.as_mut() {
+        scripts.extend(zip_script_entries(archive)?.into_iter().map(|(index, name)| ImportEntry::Zip { index, name }));
+    } else {
+        let mut files = Vec::new();
+        for entry in std::fs::read_dir(unpacked).ctx("listing", unpacked)? {                       // :contentReference[oaicite:3]{index=3}
+            let p = entry.ctx("listing", unpacked)?.path();
+            if p.extension().and_then(|e| e.to_str()) == Some("json") { // skip manifests
+                continue;
+            }
+            if !p.is_file() { continue; }                              // guard against stray dirs
+            files.push(p);
+        }
+        files.sort();
+        scripts.extend(files.into_iter().map(ImportEntry::File));
+    }
+
+    // dependencies first, going by the sidecars `export` put next to the scripts
+    let names: Vec<String> = scripts.iter().map(|e| match e {
+        ImportEntry::File(p) => p.file_name().unwrap().to_string_lossy().into_owned(),
+        ImportEntry::Zip { name, .. } => name.clone(),
+    }).collect();
+    let mut sidecars: Vec<Option<Manifest>> = scripts.iter().map(|e| archived_sidecar(e, archive.as_mut())).collect();
+    let requires: Vec<Vec<String>> = sidecars.iter().map(|m| m.as_ref().map(|m| m.requires_plugins.clone()).unwrap_or_default()).collect();
+    let order = dependency_order(&names, &requires);
+
+    let total = scripts.len();
+    for (i, &k) in order.iter().enumerate() {
+        let entry = &scripts[k];
+        // an in-memory entry lands in its own private dir just long enough
+        // for `--manifest` and the copy into plugin_dir()
+        let staging;
+        let path = match entry {
+            ImportEntry::File(p) => p.clone(),
+            ImportEntry::Zip { index, name } => {
+                staging = tempfile::tempdir()?;
+                let dest = staging.path().join(name);
+                let mut src = archive.as_mut().unwrap().by_index(*index)?;
+                std::io::copy(&mut src, &mut std::fs::File::create(&dest).ctx("creating", &dest)?)?;
+                dest
+            }
+        };
+        let p = path.as_path();
+        let file = p.file_name().unwrap().to_string_lossy();
+        progress("import", "validating", serde_json::json!({ "file": file, "index": i + 1, "total": total }));
+        let started = Instant::now();
+        // the archived sidecar wins; `--manifest` only runs for a bare script
+        let sidecar = sidecars[k].take();
+        let entry_opts = sidecar.as_ref().map(archived_opts).unwrap_or_default();
+        let result = match sidecar {
+            Some(m) => Ok(m),
+            None if no_validate => Err("no archived sidecar, which --no-validate needs".into()),
+            None => resolve_manifest(p, &opts),
+        };
+        timings.push((file.to_string(), started.elapsed()));
+
+        let checked = result.and_then(|m| {
+            if !no_validate {
+                check_manifest(&m)?;
+                check_dependencies(&m, &opts)?;
+            }
+            Ok(m)
+        });
+        let mut m = match checked {
+            Ok(m) => m,
+            Err(e) => { diag!("⚠️  Skipped {}: {e}", p.display()); failed += 1; continue; }
+        };
+
+        if policy == OverwritePolicy::Rename
+            && let Some(old) = rename_if_taken(&mut m)
+        {
+            say(format_args!("🔀  Renamed incoming {old} → {}", m.name));
+        }
+        let existing = read_sidecar(&m.name).map(|e| e.version);
+        let counter = match (&existing, policy) {
+            (None, _) => &mut added,
+            (Some(_), OverwritePolicy::Replace) => &mut replaced,
+            (Some(have), OverwritePolicy::Upgrade)
+                if cmp_versions(&m.version, have) == Some(std::cmp::Ordering::Greater) => &mut upgraded,
+            (Some(have), _) => {
+                say(format_args!("⏭️  Kept {} v{have} (archive has v{})", m.name, m.version));
+                skipped += 1;
+                continue;
+            }
+        };
+        progress("import", "copying", serde_json::json!({ "name": m.name, "index": i + 1, "total": total }));
+        match install_plugin(p, m, &entry_opts) {
+            Ok(m) => {
+                let script = plugin_dir().join(&m.name);
+                match existing {
+                    Some(have) => report(&script, format_args!("🔁  Replaced {} v{have} → v{}", m.name, m.version)),
+                    None       => report(&script, format_args!("➕  Imported {}", m.name)),
+                }
+                *counter += 1;
+            }
+            Err(e) => { diag!("⚠️  Skipped {}: {e}", p.display()); failed += 1; }
         }
-        if !p.is_file() { continue; }                                  // guard against stray dirs
+    }
+    progress("import", "done", serde_json::json!({
+        "imported": added, "replaced": replaced, "upgraded": upgraded, "skipped": skipped, "failed": failed,
+    }));
+    say(format_args!("{added} imported, {replaced} replaced, {upgraded} upgraded, {skipped} skipped, {failed} failed"));
 
-        match validate_and_copy(&p) {                                  // reuse your existing checks
-            Ok(m) => println!("➕  Imported {}", m.name),
-            Err(e) => eprintln!("⚠️  Skipped {}: {e}", p.display()),
+    if verbose() && !timings.is_empty() {
+        timings.sort_by_key(|t| std::cmp::Reverse(t.1));             // slowest first
+        diag!("⏱  manifest times:");
+        for (name, took) in &timings {
+            diag!("   {:>7}  {name}", fmt_duration(*took));
         }
     }
     Ok(())
@@ -261,7 +4961,10 @@ fn import_plugins(zip_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
 /* ---------- check if python is installed ---------- */
 
 fn current_python_version() -> Option<String> {
-    let candidates = ["python3", "python"];
+    let candidates: Vec<PathBuf> = match resolve_python_bin() {
+        Some(bin) => vec![bin.value],
+        None => vec!["python3".into(), "python".into()],
+    };
     for exe in &candidates {
         if let Ok(out) = Cmd::new(exe).arg("--version").output() {
             // stdout on *nix, stderr on Windows; concatenate for safety
@@ -279,7 +4982,7 @@ fn current_python_version() -> Option<String> {
 
 /* ---------- check if uv is installed ---------- */
 fn current_uv_version() -> Option<String> {
-    if let Ok(out) = Cmd::new("uv").arg("--version").output() {
+    if let Ok(out) = uv().arg("--version").output() {
         // output is like `uv 0.7.14`
         let text = String::from_utf8_lossy(&out.stdout);
         if text.starts_with("uv ") {
@@ -289,22 +4992,24 @@ fn current_uv_version() -> Option<String> {
     None
 }
 
-fn install_python() -> Result<(), Box<dyn std::error::Error>> {
-    let target = "3.13.3";
+fn install_python(target: &str) -> Result<(), Box<dyn std::error::Error>> {
+    require_online("installing Python")?;
     let os = std::env::consts::OS;
+    // package managers index by minor version: 3.13.3 -> 3.13 / 313
+    let minor = target.splitn(3, '.').take(2).collect::<Vec<_>>().join(".");
 
     match os {
         "windows" => {
             // prefer winget (Win 11 / Server 2022)
             if Cmd::new("where").arg("winget").output().is_ok() {
                 let status = Cmd::new("winget")
-                    .args(["install", "--id=Python.Python.3.13", "-e"])
+                    .args(["install", &format!("--id=Python.Python.{minor}"), "-e"])
                     .status()?;
                 if status.success() { return Ok(()); }
             }
             // fall back to Chocolatey
             let status = Cmd::new("choco")
-                .args(["install", "python313", "--yes"])
+                .args(["install", &format!("python{}", minor.replace('.', "")), "--yes"])
                 .status()?;
             if status.success() { return Ok(()); }
             Err("winget/choco installation failed".into())
@@ -312,7 +5017,7 @@ fn install_python() -> Result<(), Box<dyn std::error::Error>> {
         "macos" => {
             if Cmd::new("which").arg("brew").status()?.success() {
                 let status = Cmd::new("brew")
-                    .args(["install", "python@3.13"])
+                    .args(["install", &format!("python@{minor}")])
                     .status()?;
                 if status.success() { return Ok(()); }
             }
@@ -325,7 +5030,7 @@ fn install_python() -> Result<(), Box<dyn std::error::Error>> {
 
 fn install_with_pyenv(version: &str) -> Result<(), Box<dyn std::error::Error>> {
     // install pyenv if missing
-    if Cmd::new("which").arg("pyenv").status()?.success() == false {
+    if !Cmd::new("which").arg("pyenv").status()?.success() {
         println!("→ installing pyenv (curl | bash) …");
         Cmd::new("bash")
             .arg("-c")
@@ -352,6 +5057,7 @@ fn install_with_pyenv(version: &str) -> Result<(), Box<dyn std::error::Error>> {
 }
 
 fn install_uv() -> Result<(), Box<dyn std::error::Error>> {
+    require_online("installing uv")?;
     let os = std::env::consts::OS;
 
     match os {
@@ -402,25 +5108,46 @@ fn build_cli() -> Command {
         .trailing_var_arg(true)          // captures --flags etc. :contentReference[oaicite:1]{index=1}
         .help("arguments forwarded to the plugin");
 
-    for m in load_manifests() {          // parses *.json on disk
-        // leak top-level strings
-        let pname: &'static str = Box::leak(m.name.clone().into_boxed_str());
-        let pdesc: &'static str = Box::leak(m.description.clone().into_boxed_str());
-
-        let mut plug = Command::new(pname).about(pdesc);
+    let plugins = load_manifests();      // parses *.json on disk
+    let collisions = cli_collisions(&plugins);
+    if verbose() {
+        for c in &collisions {
+            diag!("⚠️  `{}` of plugin `{}` is already {}; ignored (see `uni doctor`)", c.word, c.plugin, c.taken_by);
+        }
+    }
+    let mut seen = std::collections::HashSet::new();
+    for mut m in plugins {
+        if !seen.insert(m.name.clone()) {
+            continue;                    // second sidecar claiming the same name
+        }
+        m.aliases.retain(|a| !collisions.iter().any(|c| c.plugin == m.name && &c.word == a));
+        if m.dynamic_commands {
+            m.commands = dynamic_commands(&m);
+        }
+        // owned strings (clap's `string` feature): `uni shell` rebuilds this
+        // tree on every TAB, so nothing here may leak
+        let mut plug = Command::new(m.name.clone()).about(m.description.clone()).hide(m.hidden).visible_aliases(m.aliases.clone());
+        if !m.examples.is_empty() {
+            plug = plug.after_help(format!("Examples:\n  {}", m.examples.join("\n  ")));
+        }
 
         for sc in &m.commands {
-            let sname: &'static str = Box::leak(sc.name.clone().into_boxed_str());
-            let sdesc: &'static str = Box::leak(sc.description.clone().into_boxed_str());
-
-            plug = plug.subcommand(
-                Command::new(sname).about(sdesc).arg(trailing.clone())
-            );                           // nested sub-commands :contentReference[oaicite:2]{index=2}
+            let sub = Command::new(sc.name.clone()).about(sc.description.clone());
+            let sub = match &sc.args {
+                // uni knows these args, so its own --help describes them
+                Some(specs) => sub.args(specs.iter().map(ArgSpec::to_arg)),
+                // free-form: `uni deploy run --help` (and any other flag) is the
+                // plugin's to answer; `uni deploy --help` still lists subcommands
+                None => sub.disable_help_flag(true).arg(trailing.clone().allow_hyphen_values(true)),
+            };
+            plug = plug.subcommand(sub);  // nested sub-commands :contentReference[oaicite:2]{index=2}
         }
 
         // If no commands declared, still add trailing args at top level.
         if m.commands.is_empty() {
             plug = plug.arg(trailing.clone());
+        } else if m.allow_any_subcommand {
+            plug = plug.allow_external_subcommands(true);
         }
 
         cmd = cmd.subcommand(plug);      // insert into tree
@@ -428,35 +5155,250 @@ fn build_cli() -> Command {
     cmd
 }
 
+/* ---------- plugin argv ---------- */
+
+// `args` exists only where build_cli attached the trailing arg; clap files
+// an external (undeclared) subcommand's arguments under the empty id
+fn raw_args(m: &clap::ArgMatches, id: &str) -> Vec<std::ffi::OsString> {
+    m.try_get_raw(id).ok().flatten().into_iter().flatten().map(Into::into).collect()
+}
+
+/// Rebuild the command line clap took apart for a subcommand with typed args.
+fn typed_argv(specs: &[ArgSpec], sm: &clap::ArgMatches) -> Vec<std::ffi::OsString> {
+    let mut argv = Vec::new();
+    for a in specs {
+        let flag = std::ffi::OsString::from(format!("--{}", a.name));
+        if a.is_switch() {
+            if sm.get_flag(&a.name) { argv.push(flag); }
+            continue;
+        }
+        for v in raw_args(sm, &a.name) {
+            if a.long { argv.push(flag.clone()); }
+            argv.push(v);
+        }
+    }
+    argv
+}
+
 /* ---------- main ---------- */
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    ensure_plugin_dir()?;
+fn main() {
+    if let Err(e) = run() {
+        diag!("Error: {e}");
+        exit(1);
+    }
+}
+
+fn run() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<std::ffi::OsString> = std::env::args_os().collect();
+    // first: a relative `--config`, the plugin dir and every path argument
+    // then resolve against the new directory, as with `git -C`
+    let leading = leading_flags(&args);
+    if let Some(dir) = &leading.chdir {
+        std::env::set_current_dir(dir).ctx("--chdir: changing to", dir)?;
+    }
+    if let Some(p) = &leading.config
+        && !p.is_file()
+    {
+        return Err(format!("--config {}: no such file", p.display()).into());
+    }
+    let _ = CONFIG_FLAG.set(leading.config.clone());
+
+    // We need matches twice: once for built-ins, once for plugins.  With
+    // `@name`, clap only sees the global flags in front of it
+    let at = at_plugin(&args);
+    let matches = match at {
+        Some(i) => Cli::command().get_matches_from(&args[..i]),
+        None => build_cli().get_matches(),
+    };
+    // the pre-scan is what took effect; make sure clap read the same thing
+    for (id, flag, seen) in [("chdir", "--chdir", &leading.chdir), ("config", "--config", &leading.config)] {
+        if let Some(p) = matches.get_one::<PathBuf>(id)
+            && seen.as_ref() != Some(p)
+        {
+            return Err(format!("{flag} {}: not applied; put it before the subcommand", p.display()).into());
+        }
+    }
+    if let Some(path) = matches.get_one::<PathBuf>("log_file") {
+        open_log(path)?;
+    }
+    let _ = GLOBAL_FLAGS.set(GlobalFlags {
+        offline: matches.get_flag("offline"),
+        color: matches.get_one::<ColorMode>("color").copied(),
+        verbose: matches.get_flag("verbose"),
+        quiet: matches.get_flag("quiet"),
+        progress_json: matches.get_flag("progress_json"),
+        porcelain: matches.get_flag("porcelain"),
+        profile: matches.get_one::<String>("profile").cloned(),
+    });
+    // doctor reports (and maybe recreates) a missing dir itself; list just
+    // says nothing is installed rather than create a dir from a bad override
+    if !matches!(matches.subcommand_name(), Some("doctor" | "list")) {
+        ensure_plugin_dir()?;
+    }
+    if matches.subcommand_name() != Some("doctor") {
+        warn_network_fs_once();
+    }
 
-    // We need matches twice: once for built-ins, once for plugins
-    let matches = build_cli().get_matches();
+    if let Some(i) = at {
+        let word = args[i].to_string_lossy();
+        let name = &word[1..];
+        if name.is_empty() || Path::new(name).file_name() != Some(std::ffi::OsStr::new(name)) {
+            return Err(format!("`{word}`: expected @<plugin name>").into());
+        }
+        let manifest = read_sidecar(name).ok_or_else(|| format!("no plugin `{name}` (from `{word}`)"))?;
+        exit(launch_plugin(name, Some(&manifest), &args[i + 1..])?);
+    }
 
     // 1) Handle built-in subcommands if any
     if let Some(("add",  sub_m)) = matches.subcommand() {
-        let path = sub_m.get_one::<PathBuf>("path").unwrap();
-        let m = validate_and_copy(path)?;
-        println!("Added plugin `{}` v{}", m.name, m.version);
+        let source = match sub_m.get_one::<String>("from_git") {
+            Some(url) => git_source(
+                url,
+                sub_m.get_one::<String>("git_ref").map(String::as_str),
+                sub_m.get_one::<String>("script").unwrap(),
+            ),
+            None if sub_m.contains_id("from_pypi") => pypi_source(
+                sub_m.get_one::<String>("from_pypi").unwrap(),
+                sub_m.get_one::<String>("entry_point").map(String::as_str),
+            ),
+            None => {
+                let path = sub_m.get_one::<PathBuf>("path").unwrap();
+                match path.to_str() {
+                    Some(url) if is_url(url) => url.to_owned(),
+                    _ => fs::canonicalize(path).ctx("resolving", path)?.to_string_lossy().into_owned(),
+                }
+            }
+        };
+        if sub_m.get_flag("link") && (is_url(&source) || source.starts_with("git+")) {
+            return Err("--link needs a local script".into());
+        }
+        let executable = sub_m.get_flag("executable");
+        if sub_m.contains_id("name") && !executable && !sub_m.get_flag("allow_manifest_failure") {
+            return Err("--name needs --allow-manifest-failure or --executable".into());
+        }
+        let opts = InstallOpts {
+            link: sub_m.get_flag("link"),
+            tags: sub_m.get_many::<String>("tags").unwrap_or_default().cloned().collect(),
+            description: sub_m.get_one::<String>("description").cloned(),
+            allow_manifest_failure: sub_m.get_flag("allow_manifest_failure"),
+            name: sub_m.get_one::<String>("name").cloned(),
+            executable,
+            force: sub_m.get_flag("force"),
+            pre_hook: sub_m.get_one::<String>("pre").cloned(),
+            post_hook: sub_m.get_one::<String>("post").cloned(),
+            hook_warn_only: sub_m.get_flag("hook_warn_only"),
+            subcommands: sub_m.get_many::<SubCmdMeta>("subcommands").unwrap_or_default().cloned().collect(),
+            uv_args: sub_m.get_many::<String>("uv_args").unwrap_or_default().cloned().collect(),
+            overwrite_backup: sub_m.get_flag("overwrite_backup"),
+            smoke_test: sub_m.get_flag("smoke_test"),
+            extra: sub_m.get_many::<(String, String)>("extra").unwrap_or_default().cloned().collect(),
+            skip_interpreter_check: sub_m.get_flag("no_interpreter_check"),
+            pin: sub_m.get_flag("pin"),
+            manifest_timeout: sub_m.get_one::<u64>("manifest_timeout").map(|s| Duration::from_secs(*s)),
+            no_exec: !executable && (sub_m.get_flag("no_exec") || config().no_exec.unwrap_or(false)),
+            validate_only: sub_m.get_flag("validate_only") || sub_m.get_flag("dry_run"),
+            checksum: sub_m.get_one::<String>("checksum").cloned(),
+            rename_on_collision: sub_m.get_flag("rename_on_collision"),
+            ..Default::default()
+        };
+        if sub_m.get_flag("dry_run") {
+            let m = install_from_source(&source, &opts)?;
+            return print_install_plan(Path::new(&source), m, &opts);     // only --link reads the path
+        }
+        let m = if opts.validate_only {
+            install_from_source(&source, &opts)?
+        } else {
+            audited("add", &[], Some(source.clone()), || install_from_source(&source, &opts))?
+        };
+        if opts.validate_only {
+            let note = if read_sidecar(&m.name).is_some() { " (would replace the installed one)" } else { "" };
+            report(
+                &plugin_dir().join(&m.name),
+                format_args!("✅ `{}` v{} is valid, {} subcommand(s){note}", m.name, m.version, m.commands.len()),
+            );
+            return Ok(());
+        }
+        report(&plugin_dir().join(&m.name), format_args!("Added plugin `{}` v{}", m.name, m.version));
         return Ok(());
     }
     if let Some(("remove", sub_m)) = matches.subcommand() {
+        if sub_m.get_flag("stdin") {
+            let remove = || remove_from_stdin(
+                sub_m.get_flag("purge"),
+                sub_m.get_flag("dry_run"),
+                sub_m.get_flag("yes"),
+                sub_m.get_flag("keep_going"),
+            );
+            if sub_m.get_flag("dry_run") {
+                remove()?;
+            } else {
+                audited("remove", &[], None, remove)?;
+            }
+            return Ok(());
+        }
         let name = sub_m.get_one::<String>("name").unwrap();
-        remove_plugin(name)?;
-        println!("Removed plugin `{}`", name);
+        if sub_m.get_flag("dry_run") {
+            let targets = removal_targets(name, sub_m.get_flag("purge"))?;
+            for p in &targets {
+                println!("{}", p.display());
+            }
+            say(format_args!("{} path(s) would be removed", targets.len()));
+            if !targets.is_empty() {
+                exit(1);   // like `diff`: something would change
+            }
+            return Ok(());
+        }
+        audited("remove", std::slice::from_ref(name), None, || Ok(remove_plugin(name, sub_m.get_flag("purge"))?))?;
+        report(&plugin_dir().join(name), format_args!("Removed plugin `{}`", name));
         return Ok(());
     }
-    if let Some(("list", _)) = matches.subcommand() {
-        list_plugins()?;
+    if let Some(("list", sub_m)) = matches.subcommand() {
+        let filter = ListFilter {
+            range: VersionRange {
+                min: sub_m.get_one::<String>("min_version").cloned(),
+                max: sub_m.get_one::<String>("max_version").cloned(),
+            },
+            no_hidden: sub_m.get_flag("no_hidden"),
+            tag: sub_m.get_one::<String>("tag").cloned(),
+            grep: sub_m.get_one::<Regex>("grep").cloned(),
+        };
+        let selected = || load_manifests().into_iter().filter(|m| filter.keeps(m));
+        if sub_m.get_flag("broken") {
+            for line in broken_sidecars()? {
+                println!("{line}");
+            }
+            return Ok(());
+        }
+        if sub_m.get_flag("modified") {
+            for m in modified_plugins().into_iter().filter(|m| filter.keeps(m)) {
+                println!("- {}  (v{})  [modified]  {}", m.name, m.version, plugin_dir().join(&m.name).display());
+            }
+            return Ok(());
+        }
+        if sub_m.get_flag("count") {
+            println!("{}", selected().count());
+            return Ok(());
+        }
+        if let Some(tpl) = sub_m.get_one::<String>("format") {
+            list_formatted(tpl, selected().collect())?;
+            return Ok(());
+        }
+        list_plugins(sub_m.get_flag("health"), &filter)?;
         return Ok(());
     }
     if let Some(("create", sub_m)) = matches.subcommand() {
         let name = sub_m.get_one::<String>("name").unwrap();
-        match create_template(name) {
+        match create_template(name, sub_m.get_flag("static_manifest"), sub_m.get_flag("with_tests")) {
+            Ok(p) if porcelain() => println!("{}", p.display()),
+            Ok(_) if quiet() => {}
             Ok(p) => {
+                if sub_m.get_flag("static_manifest") {
+                    println!("Created manifest at {}", p.with_extension("json").display());
+                }
+                if sub_m.get_flag("with_tests") {
+                    println!("Created tests at {}   # run with `uvx pytest`", test_path(&p).display());
+                }
                 println!(
                     "Created template at {}\n\
                     ->  vim {}   # edit, test, iterate\n\
@@ -464,38 +5406,258 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     p.display(), p.display(), p.display()
                 );
             }
-            Err(e) => eprintln!("Failed to write template: {e}"),
+            Err(e) => diag!("Failed to write template: {e}"),
         }
         return Ok(());
     }
 
     if let Some(("export", sub)) = matches.subcommand() {
-        let path = sub.get_one::<PathBuf>("file").unwrap();
-        export_plugins(path)?;
+        let exclude: Vec<String> = sub.get_many::<String>("exclude").unwrap_or_default().cloned().collect();
+        let exported: Vec<String> = load_manifests()
+            .into_iter()
+            .map(|m| m.name)
+            .filter(|n| !exclude.iter().any(|p| glob_match(p, n)))
+            .collect();
+        if let Some(dir) = sub.get_one::<PathBuf>("to_dir") {
+            let dest = Some(dir.display().to_string());
+            audited("export", &exported, dest, || export_to_dir(dir, sub.get_flag("manifest_only"), &exclude))?;
+            return Ok(());
+        }
+        if sub.get_flag("split") {
+            let (_, format) = export_target(None, sub.get_one::<ArchiveFormat>("format").copied());
+            let out_dir = sub.get_one::<PathBuf>("out_dir").unwrap();
+            let dest = Some(out_dir.display().to_string());
+            audited("export", &exported, dest, || export_split(out_dir, format, &exclude))?;
+            return Ok(());
+        }
+        let (path, format) = export_target(
+            sub.get_one::<PathBuf>("file"),
+            sub.get_one::<ArchiveFormat>("format").copied(),
+        );
+        let dest = Some(path.display().to_string());
+        audited("export", &exported, dest, || export_plugins(&path, format, sub.get_flag("manifest_only"), &exclude))?;
         return Ok(());
     }
 
     if let Some(("import", sub)) = matches.subcommand() {
-        let path = sub.get_one::<PathBuf>("file").unwrap();
-        import_plugins(path)?;
+        let tmp = tempfile::tempdir()?;
+        let path = match sub.get_one::<String>("from_url") {
+            Some(url) => download(url, tmp.path())?,
+            None => sub.get_one::<PathBuf>("file").unwrap().clone(),
+        };
+        let origin = sub.get_one::<String>("from_url").cloned().unwrap_or_else(|| path.display().to_string());
+        audited("import", &[], Some(origin), || {
+            if let Some(want) = sub.get_one::<String>("checksum") {
+                verify_checksum(&path, want)?;
+            }
+            import_plugins(
+                &path,
+                *sub.get_one::<OverwritePolicy>("overwrite_policy").unwrap(),
+                sub.get_flag("in_memory"),
+                sub.get_flag("no_validate"),
+            )
+        })?;
+        return Ok(());
+    }
+
+    if let Some(("info", sub_m)) = matches.subcommand() {
+        let name = sub_m.get_one::<String>("name").unwrap();
+        if sub_m.get_flag("history") {
+            show_history(name)?;
+        } else {
+            show_info(name, sub_m.get_flag("raw"))?;
+        }
+        return Ok(());
+    }
+
+    if let Some(("rollback", sub_m)) = matches.subcommand() {
+        let name = sub_m.get_one::<String>("name").unwrap();
+        let m = audited("rollback", std::slice::from_ref(name), None, || rollback_plugin(name))?;
+        report(&plugin_dir().join(name), format_args!("Rolled `{name}` back to v{}", m.version));
+        return Ok(());
+    }
+
+    if let Some(("move", sub_m)) = matches.subcommand() {
+        let name = sub_m.get_one::<String>("name").unwrap();
+        let to = sub_m.get_one::<PathBuf>("dir").unwrap();
+        audited("move", std::slice::from_ref(name), Some(to.display().to_string()), || move_plugin(name, to))?;
+        report(&to.join(name), format_args!("Moved `{name}` to {}", to.display()));
+        return Ok(());
+    }
+
+    if let Some(("restore", sub_m)) = matches.subcommand() {
+        let name = sub_m.get_one::<String>("name").unwrap();
+        if sub_m.get_flag("list") {
+            show_backups(name);
+            return Ok(());
+        }
+        let from = sub_m.get_one::<String>("from").map(String::as_str);
+        let m = audited("restore", std::slice::from_ref(name), None, || restore_plugin(name, from))?;
+        report(&plugin_dir().join(name), format_args!("Restored `{name}` v{}", m.version));
+        return Ok(());
+    }
+
+    if let Some((cmd @ ("pin" | "unpin"), sub_m)) = matches.subcommand() {
+        let name = sub_m.get_one::<String>("name").unwrap();
+        audited(cmd, std::slice::from_ref(name), None, || set_pinned(name, cmd == "pin"))?;
+        report(
+            &plugin_dir().join(format!("{name}.json")),
+            format_args!("{} plugin `{name}`", if cmd == "pin" { "Pinned" } else { "Unpinned" }),
+        );
+        return Ok(());
+    }
+
+    if let Some(("set", sub_m)) = matches.subcommand() {
+        let name = sub_m.get_one::<String>("name").unwrap();
+        let pairs: Vec<(String, String)> = sub_m.get_many::<(String, String)>("pairs").unwrap().cloned().collect();
+        audited("set", std::slice::from_ref(name), None, || set_extra(name, &pairs, &[]))?;
+        report(&plugin_dir().join(format!("{name}.json")), format_args!("Set {} key(s) on `{name}`", pairs.len()));
+        return Ok(());
+    }
+
+    if let Some(("unset", sub_m)) = matches.subcommand() {
+        let name = sub_m.get_one::<String>("name").unwrap();
+        let keys: Vec<String> = sub_m.get_many::<String>("keys").unwrap().cloned().collect();
+        audited("unset", std::slice::from_ref(name), None, || set_extra(name, &[], &keys))?;
+        report(&plugin_dir().join(format!("{name}.json")), format_args!("Unset {} key(s) on `{name}`", keys.len()));
+        return Ok(());
+    }
+
+    if let Some(("diff", sub_m)) = matches.subcommand() {
+        let name = sub_m.get_one::<String>("name").unwrap();
+        diff_plugin(name, sub_m.get_one::<PathBuf>("script").unwrap())?;
+        return Ok(());
+    }
+
+    if let Some(("prune", sub_m)) = matches.subcommand() {
+        if sub_m.get_flag("dry_run") {
+            prune(true, false)?;
+        } else {
+            audited("prune", &[], None, || prune(false, sub_m.get_flag("yes")))?;
+        }
+        return Ok(());
+    }
+
+    if let Some(("audit", sub_m)) = matches.subcommand() {
+        let ops: Vec<String> = sub_m.get_many::<String>("ops").unwrap_or_default().cloned().collect();
+        show_audit(&ops, sub_m.get_one::<usize>("tail").copied(), sub_m.get_flag("json"))?;
+        return Ok(());
+    }
+
+    if let Some(("doctor", sub_m)) = matches.subcommand() {
+        let skip: Vec<String> = sub_m.get_many::<String>("skip").unwrap_or_default().cloned().collect();
+        doctor(sub_m.get_flag("fix"), sub_m.get_flag("yes"), &skip, sub_m.get_flag("json"))?;
+        return Ok(());
+    }
+
+    if let Some(("__complete", sub_m)) = matches.subcommand() {
+        if let Some(shell) = sub_m.get_one::<String>("script") {
+            print!("{}", completion_script(shell));
+            return Ok(());
+        }
+        let words: Vec<String> = sub_m.get_many::<String>("words").unwrap_or_default().cloned().collect();
+        for c in complete(&words) {
+            println!("{c}");
+        }
+        return Ok(());
+    }
+
+    if let Some(("selftest", sub_m)) = matches.subcommand() {
+        selftest(
+            sub_m.get_one::<PathBuf>("script").unwrap(),
+            Duration::from_secs(*sub_m.get_one::<u64>("timeout").unwrap()),
+        )?;
+        return Ok(());
+    }
+
+    if let Some(("inspect", sub_m)) = matches.subcommand() {
+        inspect_script(sub_m.get_one::<PathBuf>("script").unwrap())?;
+        return Ok(());
+    }
+
+    if let Some(("validate-all", sub_m)) = matches.subcommand() {
+        validate_all(sub_m.get_flag("parallel"))?;
+        return Ok(());
+    }
+
+    if let Some(("stats", sub_m)) = matches.subcommand() {
+        stats(sub_m.get_flag("disk"))?;
+        return Ok(());
+    }
+
+    if let Some(("graph", sub_m)) = matches.subcommand() {
+        plugin_graph(sub_m.get_flag("dot"))?;
+        return Ok(());
+    }
+
+    if let Some(("shell", _)) = matches.subcommand() {
+        run_shell()?;
+        return Ok(());
+    }
+
+    if let Some(("call", sub_m)) = matches.subcommand() {
+        call_plugin(
+            sub_m.get_one::<String>("name").unwrap(),
+            sub_m.get_one::<String>("request").unwrap(),
+        )?;
+        return Ok(());
+    }
+
+    if let Some(("bench", sub_m)) = matches.subcommand() {
+        let name = sub_m.get_one::<String>("name").unwrap();
+        let mut argv: Vec<std::ffi::OsString> = sub_m.get_one::<String>("subcommand").map(Into::into).into_iter().collect();
+        argv.extend(sub_m.get_many::<std::ffi::OsString>("args").unwrap_or_default().cloned());
+        bench_plugin(name, &argv, *sub_m.get_one::<usize>("runs").unwrap(), sub_m.get_flag("json"))?;
+        return Ok(());
+    }
+
+    if let Some(("schema", _)) = matches.subcommand() {
+        println!("{}", serde_json::to_string_pretty(&manifest_schema())?);
+        return Ok(());
+    }
+
+    if let Some(("config", _)) = matches.subcommand() {
+        show_config();
+        return Ok(());
+    }
+
+    if let Some(("freeze", _)) = matches.subcommand() {
+        freeze_plugins()?;
+        return Ok(());
+    }
+
+    if let Some(("sync", sub_m)) = matches.subcommand() {
+        let lockfile = sub_m.get_one::<PathBuf>("lockfile").unwrap();
+        let sync = || sync_plugins(
+            lockfile,
+            sub_m.get_flag("prune"),
+            sub_m.get_flag("dry_run"),
+            sub_m.get_flag("force"),
+        );
+        if sub_m.get_flag("dry_run") {
+            sync()?;
+        } else {
+            audited("sync", &[], Some(lockfile.display().to_string()), sync)?;
+        }
         return Ok(());
     }
 
     if let Some(("ensure-python", sub_m)) = matches.subcommand() {
         let force = *sub_m.get_one::<bool>("force").unwrap();
 
-        /* ---------- 3.1 ensure CPython 3.13.3 ---------- */
+        /* ---------- 3.1 ensure CPython (3.13.3 unless configured) ---------- */
+        let target = resolve_python_version().value;
         let need_python = match current_python_version() {
-            Some(v) if v == "3.13.3" && !force => {
-                println!("✅ Python 3.13.3 already installed"); false
+            Some(v) if v == target && !force => {
+                println!("✅ Python {target} already installed"); false
             }
-            Some(v) => { println!("ℹ️  Found Python {v}, upgrading to 3.13.3"); true }
-            None     => { println!("🚫 No python3 – installing 3.13.3"); true }
+            Some(v) => { println!("ℹ️  Found Python {v}, upgrading to {target}"); true }
+            None     => { println!("🚫 No python3 – installing {target}"); true }
         };
         if need_python {
-            match install_python() {
-                Ok(_)  => println!("🎉 Python 3.13.3 ready ✔"),
-                Err(e) => { eprintln!("❌ Python install failed: {e}"); return Ok(()); }
+            match install_python(&target) {
+                Ok(_)  => println!("🎉 Python {target} ready ✔"),
+                Err(e) => { diag!("❌ Python install failed: {e}"); return Ok(()); }
             }
         }
 
@@ -506,7 +5668,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 println!("→ installing uv …");
                 match install_uv() {
                     Ok(_)  => println!("🎉 uv installed ✔"),
-                    Err(e) => eprintln!("❌ uv install failed: {e}"),
+                    Err(e) => diag!("❌ uv install failed: {e}"),
                 }
             }
         }
@@ -518,18 +5680,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     if let Some((pname, pm)) = matches.subcommand() {
         // Ignore built-ins already handled above -----------------------------
         // Gather args and possible nested subcommand
-        let mut argv: Vec<&std::ffi::OsStr> = Vec::new();
+        let mut argv: Vec<std::ffi::OsString> = Vec::new();
+
+        let manifest = read_sidecar(pname);
 
         if let Some((sname, sm)) = pm.subcommand() {
-            argv.push(std::ffi::OsStr::new(sname));           // first token = subcmd
-            argv.extend(sm.get_raw("args").unwrap_or_default());
+            argv.push(sname.into());                          // first token = subcmd
+            argv.extend(raw_args(sm, "args"));
+            argv.extend(raw_args(sm, ""));
+            let commands = manifest.as_ref().map(|m| if m.dynamic_commands { dynamic_commands(m) } else { m.commands.clone() });
+            if let Some(specs) = commands.into_iter().flatten().find(|c| c.name == sname).and_then(|c| c.args) {
+                argv.extend(typed_argv(&specs, sm));
+            }
         } else {
-            argv.extend(pm.get_raw("args").unwrap_or_default());
+            argv.extend(raw_args(pm, "args"));
         }
 
-        let script = plugin_dir().join(pname);
-        let status = Cmd::new(script).args(&argv).status()?;
-        exit(status.code().unwrap_or(1));
+        exit(launch_plugin(pname, manifest.as_ref(), &argv)?);
     }
 
     // No subcommand at all → print help
@@ -537,3 +5704,298 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!();
     Ok(())
 }
+
+/// Run plugin `pname` with `argv` in the foreground; its exit code.
+fn launch_plugin(pname: &str, manifest: Option<&Manifest>, argv: &[std::ffi::OsString]) -> Result<i32, Box<dyn std::error::Error>> {
+    if let Some(p) = manifest.and_then(uni_version_problem) {
+        return Err(p.into());
+    }
+    if let Some(note) = manifest.and_then(|m| m.deprecated.as_deref()) {
+        // stderr only, so piped stdout stays clean
+        diag!("{}", paint(&format!("⚠️  plugin `{pname}` is deprecated: {note}"), "33"));
+    }
+    // the plugin owns the terminal: prompts, pipes and `uni x < file` all
+    // reach it directly; capturing modes (`call`, `bench`) set their own
+    use std::process::Stdio;
+    let status = plugin_command(manifest, &plugin_dir().join(pname))
+        .args(argv)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()?;
+    if !status.success() && !quiet() {
+        // so a plugin bug isn't mistaken for a uni one
+        use std::os::unix::process::ExitStatusExt;
+        match (status.code(), status.signal()) {
+            (Some(code), _) => diag!("plugin '{pname}' exited with code {code}"),
+            (None, Some(sig)) => diag!("plugin '{pname}' was killed by signal {sig}"),
+            (None, None) => diag!("plugin '{pname}' failed ({status})"),
+        }
+    }
+    Ok(status.code().unwrap_or(1))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_manifest_strips_bom_and_trailing_blank_lines() {
+        let m = parse_manifest(b"\xEF\xBB\xBF{\"name\":\"x\",\"description\":\"d\",\"version\":\"1\"}\n\n").unwrap();
+        assert_eq!((m.name.as_str(), m.version.as_str()), ("x", "1"));
+    }
+
+    #[test]
+    fn parse_manifest_rejects_non_object() {
+        let err = parse_manifest(b"[1, 2]").err().unwrap();
+        assert!(err.contains("got an array"), "{err}");
+    }
+
+    #[test]
+    fn parse_manifest_rejects_missing_name() {
+        let err = parse_manifest(b"{\"version\":\"1\"}").err().unwrap();
+        assert!(err.contains("not a manifest"), "{err}");
+    }
+
+    fn named(name: &str) -> Manifest {
+        Manifest { name: name.into(), description: "d".into(), version: "1".into(), ..Default::default() }
+    }
+
+    #[test]
+    fn manifest_problems_rejects_dotted_names() {
+        for name in [".history", ".x", ".."] {
+            assert!(!manifest_problems(&named(name)).is_empty(), "{name}");
+        }
+        assert!(manifest_problems(&named("x.y")).is_empty());
+    }
+
+    #[test]
+    fn schema_leaves_out_what_uni_writes() {
+        let schema = manifest_schema();
+        let props = schema["properties"].as_object().unwrap();
+        for key in SIDECAR_ONLY_KEYS {
+            assert!(!props.contains_key(*key), "{key}");
+        }
+        assert!(props.contains_key("requires_plugins") && props.contains_key("commands"));
+        assert_eq!(schema["required"], serde_json::json!(["name", "description", "version"]));
+    }
+
+    #[test]
+    fn parse_plugin_manifest_drops_uni_owned_keys() {
+        let json = br#"{"name":"x","description":"d","version":"1","tags":["t"],
+            "manifest_origin":"none","pinned":true,"sha256":"00","linked":true,"no_exec":true,
+            "executable":true,"custom_description":true,"source":"/elsewhere","extra":{"k":"v"}}"#;
+        let m = parse_plugin_manifest(json).unwrap();
+        assert!(m.manifest_origin == ManifestOrigin::Exec);
+        assert!(!m.pinned && !m.linked && !m.no_exec && !m.executable && !m.custom_description);
+        assert!(m.sha256.is_none() && m.source.is_none() && m.extra.is_empty());
+        assert_eq!(m.tags, ["t"]);                   // declared by the plugin: kept
+        // a sidecar uni wrote itself keeps them
+        assert!(parse_manifest(json).unwrap().pinned);
+    }
+
+    #[test]
+    fn unified_diff_is_empty_without_changes() {
+        assert_eq!(unified_diff("", "", "a", "b"), "");
+        assert_eq!(unified_diff("x\ny\n", "x\ny\n", "a", "b"), "");
+    }
+
+    #[test]
+    fn unified_diff_pure_insert_and_delete() {
+        assert_eq!(unified_diff("", "x\ny\n", "a", "b"), "--- a\n+++ b\n@@ -0,0 +1,2 @@\n+x\n+y\n");
+        assert_eq!(unified_diff("x\ny\n", "", "a", "b"), "--- a\n+++ b\n@@ -1,2 +0,0 @@\n-x\n-y\n");
+    }
+
+    // expected output as `diff -u` prints it
+    #[test]
+    fn unified_diff_hunks_and_context() {
+        let lines = |edits: &[(usize, &str)]| -> String {
+            (1..=20).map(|n| edits.iter().find(|e| e.0 == n).map_or(n.to_string(), |e| e.1.to_owned()) + "\n").collect()
+        };
+        let old = lines(&[]);
+        // changes 16 lines apart: two hunks, each with 3 lines of context
+        assert_eq!(
+            unified_diff(&old, &lines(&[(2, "two"), (18, "eighteen")]), "a", "b"),
+            "--- a\n+++ b\n@@ -1,5 +1,5 @@\n 1\n-2\n+two\n 3\n 4\n 5\n\
+             @@ -15,6 +15,6 @@\n 15\n 16\n 17\n-18\n+eighteen\n 19\n 20\n"
+        );
+        // 5 unchanged lines between them: one hunk
+        assert_eq!(
+            unified_diff(&old, &lines(&[(5, "five"), (11, "eleven")]), "a", "b"),
+            "--- a\n+++ b\n@@ -2,13 +2,13 @@\n 2\n 3\n 4\n-5\n+five\n 6\n 7\n 8\n 9\n 10\n-11\n+eleven\n 12\n 13\n 14\n"
+        );
+        assert_eq!(unified_diff("a\nb\nc\n", "a\nc\nd\n", "a", "b"), "--- a\n+++ b\n@@ -1,3 +1,3 @@\n a\n-b\n c\n+d\n");
+    }
+
+
+    #[test]
+    fn expand_user_path_tilde() {
+        let home = directories::BaseDirs::new().unwrap().home_dir().to_string_lossy().into_owned();
+        assert_eq!(expand_user_path("~"), PathBuf::from(&home));
+        assert_eq!(expand_user_path("~/bin/x"), PathBuf::from(format!("{home}/bin/x")));
+        // only the current user's home; `~user` and a `~` further in stay put
+        assert_eq!(expand_user_path("~user/x"), PathBuf::from("~user/x"));
+        assert_eq!(expand_user_path("a/~/b"), PathBuf::from("a/~/b"));
+    }
+
+    #[test]
+    fn expand_user_path_variables() {
+        let home = std::env::var("HOME").unwrap();
+        assert_eq!(expand_user_path("$HOME/x"), PathBuf::from(format!("{home}/x")));
+        assert_eq!(expand_user_path("${HOME}x"), PathBuf::from(format!("{home}x")));
+        // the name runs to the first non-word char: `$HOME_x` is another variable
+        assert_eq!(expand_user_path("$HOME_UNI_UNSET_x/y"), PathBuf::from("$HOME_UNI_UNSET_x/y"));
+        assert_eq!(expand_user_path("${UNI_UNSET_VAR}/y"), PathBuf::from("${UNI_UNSET_VAR}/y"));
+    }
+
+    #[test]
+    fn expand_user_path_leaves_stray_dollars() {
+        assert_eq!(expand_user_path("$"), PathBuf::from("$"));
+        assert_eq!(expand_user_path("a$/b"), PathBuf::from("a$/b"));
+        assert_eq!(expand_user_path("${HOME"), PathBuf::from("${HOME"));
+        assert_eq!(expand_user_path("x/${}"), PathBuf::from("x/${}"));
+    }
+
+
+    #[test]
+    fn cmp_versions_numeric_components() {
+        use std::cmp::Ordering::*;
+        assert_eq!(cmp_versions("1.10", "1.9"), Some(Greater));
+        assert_eq!(cmp_versions("1.2", "1.2.0"), Some(Equal));
+        assert_eq!(cmp_versions("1.2.1", "1.2"), Some(Greater));
+        assert_eq!(cmp_versions("v2.0", "1.99.99"), Some(Greater));
+        assert_eq!(cmp_versions("1.0.0+build.5", "1.0.0"), Some(Equal));
+    }
+
+    #[test]
+    fn cmp_versions_pre_releases() {
+        use std::cmp::Ordering::*;
+        assert_eq!(cmp_versions("1.0.0-rc.1", "1.0.0"), Some(Less));
+        assert_eq!(cmp_versions("1.0.0-alpha", "1.0.0-beta"), Some(Less));
+        assert_eq!(cmp_versions("1.0.1-rc.1", "1.0.0"), Some(Greater));
+    }
+
+    #[test]
+    fn cmp_versions_non_numeric_is_none() {
+        assert_eq!(cmp_versions("dev", "1.0"), None);
+        assert_eq!(cmp_versions("1.0", "1.x"), None);
+        assert_eq!(cmp_versions("", "1"), None);
+        assert_eq!(cmp_versions("1..2", "1"), None);
+    }
+
+
+    #[test]
+    fn glob_match_wildcards() {
+        assert!(glob_match("local-*", "local-db") && glob_match("local-*", "local-"));
+        assert!(!glob_match("local-*", "xlocal-db"));
+        assert!(glob_match("*-db", "local-db") && glob_match("a*b*c", "aXbYbc"));
+        assert!(glob_match("t?st", "test") && !glob_match("t?st", "tst") && !glob_match("t?st", "teest"));
+        assert!(glob_match("*", "") && glob_match("**", "anything"));
+        assert!(glob_match("exact", "exact") && !glob_match("exact", "exactly"));
+    }
+
+    #[test]
+    fn glob_match_empty_pattern_matches_only_empty() {
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "x"));
+    }
+
+
+    fn leading(words: &[&str]) -> (Option<PathBuf>, Option<PathBuf>) {
+        let args: Vec<std::ffi::OsString> = std::iter::once("uni").chain(words.iter().copied()).map(Into::into).collect();
+        let flags = leading_flags(&args);
+        (flags.chdir, flags.config)
+    }
+
+    #[test]
+    fn leading_flags_chdir_forms() {
+        let dir = Some(PathBuf::from("work"));
+        for words in [
+            &["-C", "work", "list"][..],
+            &["-Cwork", "list"],
+            &["-C=work", "list"],
+            &["--chdir", "work", "list"],
+            &["--chdir=work", "list"],
+            &["-qC", "work", "list"],
+            &["-vCwork", "list"],
+            &["--offline", "-C", "work", "list"],
+        ] {
+            assert_eq!(leading(words).0, dir, "{words:?}");
+        }
+        // last one wins, as in clap
+        assert_eq!(leading(&["-C", "a", "--chdir", "b", "list"]).0, Some(PathBuf::from("b")));
+    }
+
+    #[test]
+    fn leading_flags_config_forms() {
+        let cfg = Some(PathBuf::from("c.toml"));
+        assert_eq!(leading(&["--config", "c.toml", "list"]).1, cfg);
+        assert_eq!(leading(&["--config=c.toml", "list"]).1, cfg);
+        assert_eq!(leading(&["-C", "work", "--config", "c.toml", "list"]), (Some(PathBuf::from("work")), cfg));
+        // a valued flag's value is not mistaken for either
+        assert_eq!(leading(&["--profile", "-C", "list"]), (None, None));
+    }
+
+    #[test]
+    fn leading_flags_stop_at_the_subcommand_and_double_dash() {
+        assert_eq!(leading(&["list", "-C", "work"]), (None, None));
+        assert_eq!(leading(&["deploy", "--config", "c.toml"]), (None, None));
+        assert_eq!(leading(&["--", "-C", "work"]), (None, None));
+        assert_eq!(leading(&["-", "-C", "work"]), (None, None));
+        assert_eq!(leading(&[]), (None, None));
+    }
+
+
+    fn render(tpl: &str) -> Result<String, String> {
+        render_template(tpl, |f| format!("<{f}>"))
+    }
+
+    #[test]
+    fn render_template_placeholders_and_escapes() {
+        assert_eq!(render("{name} v{version}").unwrap(), "<name> v<version>");
+        assert_eq!(render("{{name}} {name}").unwrap(), "{name} <name>");
+        assert_eq!(render("}}{{").unwrap(), "}{");
+        assert_eq!(render("plain").unwrap(), "plain");
+        assert_eq!(render("").unwrap(), "");
+    }
+
+    #[test]
+    fn render_template_rejects_bad_templates() {
+        let err = render("{nmae}").unwrap_err();
+        assert!(err.contains("unknown placeholder `{nmae}`"), "{err}");
+        assert!(render("{}").unwrap_err().contains("unknown placeholder"));
+        assert!(render("{name").unwrap_err().contains("unclosed `{`"));
+        assert!(render("x {").unwrap_err().contains("unclosed `{`"));
+        assert!(render("name}").unwrap_err().contains("unmatched `}`"));
+    }
+
+
+
+    #[test]
+    fn sh_trampoline_runs_the_resolved_uv_unless_overridden() {
+        use std::os::unix::fs::PermissionsExt;
+        let tmp = tempfile::tempdir().unwrap();
+        let fake = |name: &str| {
+            let p = tmp.path().join(name);
+            fs::write(&p, format!("#!/bin/sh\necho {} \"$@\"\n", name.replace(['$', '"'], ""))).unwrap();
+            fs::set_permissions(&p, fs::Permissions::from_mode(0o755)).unwrap();
+            p
+        };
+        let installed = fake("my $uv \"x\"");
+        let overridden = fake("other");
+        let script = tmp.path().join("plugin");
+        fs::write(&script, format!("{}print('hi')\n", sh_trampoline(&installed))).unwrap();
+
+        let run = |env: Option<&Path>| {
+            let mut cmd = Cmd::new("/bin/sh");
+            cmd.arg(&script).arg("a b").env_remove("UNI_UV_BIN");
+            if let Some(uv) = env {
+                cmd.env("UNI_UV_BIN", uv);
+            }
+            String::from_utf8(cmd.output().unwrap().stdout).unwrap()
+        };
+        let script = script.display();
+        assert_eq!(run(None), format!("my uv x run --script {script} a b\n"));
+        assert_eq!(run(Some(&overridden)), format!("other run --script {script} a b\n"));
+    }
+}
@@ -0,0 +1,129 @@
+//! `uni export` / `uni import` round trips through the built binary.
+
+mod common;
+
+use common::{install, mode, uni};
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+
+/// A home, a plugin dir holding `hello` (0o755) and a `no_exec` `quiet`
+/// (0o644), and an empty plugin dir to import into.
+fn setup() -> (tempfile::TempDir, std::path::PathBuf, std::path::PathBuf) {
+    let tmp = tempfile::tempdir().unwrap();
+    let (from, to) = (tmp.path().join("from"), tmp.path().join("to"));
+    fs::create_dir_all(&from).unwrap();
+    fs::create_dir_all(&to).unwrap();
+    install(&from, "hello", 0o755, serde_json::json!({
+        "name": "hello", "description": "says hi", "version": "1.0.0",
+        "tags": ["ci"], "pinned": true, "source": "/src/hello", "extra": { "team": "infra" },
+    }));
+    install(&from, "quiet", 0o644, serde_json::json!({
+        "name": "quiet", "description": "my words", "version": "2.0.0",
+        "no_exec": true, "custom_description": true,
+    }));
+    (tmp, from, to)
+}
+
+fn sidecar(dir: &Path, name: &str) -> serde_json::Value {
+    serde_json::from_slice(&fs::read(dir.join(format!("{name}.json"))).unwrap()).unwrap()
+}
+
+#[test]
+fn export_keeps_script_modes_in_the_zip() {
+    let (tmp, from, _) = setup();
+    let zip_path = tmp.path().join("plugins.zip");
+    uni(tmp.path(), &from, &["export", zip_path.to_str().unwrap()]);
+
+    let mut zip = zip::ZipArchive::new(fs::File::open(&zip_path).unwrap()).unwrap();
+    let mut entry_mode = |name: &str| zip.by_name(name).unwrap().unix_mode().unwrap() & 0o777;
+    assert_eq!(entry_mode("hello"), 0o755);
+    assert_eq!(entry_mode("hello.json"), 0o644);
+    assert_eq!(entry_mode("quiet"), 0o644);
+    assert_eq!(entry_mode("quiet.json"), 0o644);
+}
+
+#[test]
+fn import_restores_modes_and_sidecar_fields() {
+    let (tmp, from, to) = setup();
+    let zip_path = tmp.path().join("plugins.zip");
+    uni(tmp.path(), &from, &["export", zip_path.to_str().unwrap()]);
+    uni(tmp.path(), &to, &["import", zip_path.to_str().unwrap()]);
+
+    assert_eq!(mode(&to.join("hello")), 0o755);
+    assert_eq!(mode(&to.join("quiet")), 0o644);
+    assert_eq!(mode(&to.join("hello.json")), 0o644);
+
+    let hello = sidecar(&to, "hello");
+    assert_eq!(hello["tags"], serde_json::json!(["ci"]));
+    assert_eq!(hello["pinned"], true);
+    assert_eq!(hello["source"], "/src/hello");
+    assert_eq!(hello["extra"], serde_json::json!({ "team": "infra" }));
+    let quiet = sidecar(&to, "quiet");
+    assert_eq!(quiet["no_exec"], true);
+    assert_eq!(quiet["description"], "my words");
+}
+
+#[test]
+fn import_no_validate_copies_with_fixed_modes() {
+    let (tmp, from, to) = setup();
+    // `--no-validate` must not depend on the mode the exporter happened to have
+    fs::set_permissions(from.join("hello"), fs::Permissions::from_mode(0o700)).unwrap();
+    let out_dir = tmp.path().join("exported");
+    uni(tmp.path(), &from, &["export", "--to-dir", out_dir.to_str().unwrap()]);
+    uni(tmp.path(), &to, &["import", "--no-validate", out_dir.to_str().unwrap()]);
+
+    assert_eq!(mode(&to.join("hello")), 0o755);
+    assert_eq!(mode(&to.join("hello.json")), 0o644);
+    assert_eq!(mode(&to.join("quiet")), 0o644);
+    assert_eq!(mode(&to.join("quiet.json")), 0o644);
+}
+
+#[test]
+fn tar_gz_round_trip_keeps_modes() {
+    let (tmp, from, to) = setup();
+    let tar_path = tmp.path().join("plugins.tar.gz");
+    uni(tmp.path(), &from, &["export", "--format", "tar.gz", tar_path.to_str().unwrap()]);
+    uni(tmp.path(), &to, &["import", tar_path.to_str().unwrap()]);
+
+    assert_eq!(mode(&to.join("hello")), 0o755);
+    assert_eq!(mode(&to.join("quiet")), 0o644);
+    assert_eq!(sidecar(&to, "quiet")["no_exec"], true);
+}
+
+/// A tar.gz holding one entry, built by hand: `uni export` never writes these.
+fn hostile_tar(path: &Path, name: &[u8], kind: tar::EntryType) {
+    let gz = flate2::write::GzEncoder::new(fs::File::create(path).unwrap(), flate2::Compression::default());
+    let mut tar = tar::Builder::new(gz);
+    let mut header = tar::Header::new_old();
+    header.as_old_mut().name[..name.len()].copy_from_slice(name);   // unchecked, so `../` survives
+    header.set_entry_type(kind);
+    if kind == tar::EntryType::Symlink {
+        header.set_link_name("/etc/passwd").unwrap();
+    }
+    header.set_size(if kind == tar::EntryType::Regular { 3 } else { 0 });
+    header.set_mode(0o755);
+    header.set_cksum();
+    let data: &[u8] = if kind == tar::EntryType::Regular { b"hi\n" } else { b"" };
+    tar.append(&header, data).unwrap();
+    tar.into_inner().unwrap().finish().unwrap();
+}
+
+#[test]
+fn tar_gz_import_refuses_anything_but_top_level_files() {
+    use tar::EntryType::{Directory, Regular, Symlink};
+    for (name, kind) in [(&b"../evil"[..], Regular), (b"sub/evil", Regular), (b"/tmp/evil", Regular), (b"evil", Symlink), (b"evil", Directory)] {
+        let tmp = tempfile::tempdir().unwrap();
+        let to = tmp.path().join("to");
+        fs::create_dir_all(&to).unwrap();
+        let tar_path = tmp.path().join("bad.tar.gz");
+        hostile_tar(&tar_path, name, kind);
+
+        let out = common::run(tmp.path(), &to, &["import", tar_path.to_str().unwrap()]);
+        let label = String::from_utf8_lossy(name);
+        assert!(!out.status.success(), "{label} ({kind:?}) was accepted");
+        assert!(String::from_utf8_lossy(&out.stderr).contains("not a plain file at the top level"), "{label}");
+        assert_eq!(fs::read_dir(&to).unwrap().count(), 0, "{label}");
+        assert!(!tmp.path().join("evil").exists(), "{label}");
+    }
+}
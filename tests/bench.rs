@@ -0,0 +1,22 @@
+//! `uni bench` through the built binary.
+
+mod common;
+
+use common::{install, uni};
+use std::fs;
+
+#[test]
+fn options_after_double_dash_reach_the_plugin() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("plugins");
+    fs::create_dir_all(&dir).unwrap();
+    install(&dir, "echo", 0o755, serde_json::json!({ "name": "echo", "description": "d", "version": "1" }));
+    let log = tmp.path().join("log");
+    fs::write(dir.join("echo"), format!("#!/bin/sh\necho \"$@\" >> '{}'\n", log.display())).unwrap();
+
+    let out = uni(tmp.path(), &dir, &["bench", "echo", "run", "--runs", "2", "--", "x", "--runs", "7", "--json"]);
+    // uni took `--runs 2` and printed its table; the plugin got the rest
+    assert!(String::from_utf8_lossy(&out.stdout).starts_with("echo: 2 runs"));
+    let calls = fs::read_to_string(&log).unwrap();
+    assert_eq!(calls.lines().collect::<Vec<_>>(), ["run x --runs 7 --json"; 3]);
+}
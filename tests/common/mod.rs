@@ -0,0 +1,42 @@
+//! Helpers for the tests that drive the built `uni` binary.  Each test hands
+//! the child its own home and plugin dir, so nothing touches this process's
+//! environment.
+
+// each test crate uses only some of these
+#![allow(dead_code)]
+
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Output};
+
+/// Run `uni args…` with a clean environment rooted at `home`.
+pub fn run(home: &Path, plugin_dir: &Path, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_uni"))
+        .args(args)
+        .env_clear()
+        .env("PATH", std::env::var_os("PATH").unwrap_or_default())
+        .env("HOME", home)
+        .env("UNI_PLUGIN_DIR", plugin_dir)
+        .output()
+        .unwrap()
+}
+
+/// `run`, failing the test unless uni exits 0.
+pub fn uni(home: &Path, plugin_dir: &Path, args: &[&str]) -> Output {
+    let out = run(home, plugin_dir, args);
+    assert!(out.status.success(), "uni {args:?} failed:\n{}", String::from_utf8_lossy(&out.stderr));
+    out
+}
+
+pub fn mode(p: &Path) -> u32 {
+    fs::metadata(p).unwrap().permissions().mode() & 0o777
+}
+
+/// A plugin as `uni add` leaves it: `<name>` plus its `<name>.json` sidecar.
+pub fn install(dir: &Path, name: &str, script_mode: u32, sidecar: serde_json::Value) {
+    let script = dir.join(name);
+    fs::write(&script, "#!/bin/sh\necho hi\n").unwrap();
+    fs::set_permissions(&script, fs::Permissions::from_mode(script_mode)).unwrap();
+    fs::write(dir.join(format!("{name}.json")), serde_json::to_vec_pretty(&sidecar).unwrap()).unwrap();
+}
@@ -0,0 +1,38 @@
+//! `uni doctor --skip` through the built binary.
+
+mod common;
+
+use common::{install, run};
+
+/// The check ids in a `doctor --json` report run with `args`.
+fn checks(args: &[&str]) -> Vec<String> {
+    let tmp = tempfile::tempdir().unwrap();
+    let plugins = tmp.path().join("plugins");
+    std::fs::create_dir_all(&plugins).unwrap();
+    // not executable, and edited since install (its recorded sha256 is stale)
+    install(&plugins, "hello", 0o644, serde_json::json!({
+        "name": "hello", "description": "d", "version": "1", "sha256": "00",
+    }));
+    let out = run(tmp.path(), &plugins, &[&["doctor", "--json", "--skip", "uv"], args].concat());
+    let report: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    report["checks"].as_array().unwrap().iter().map(|c| c["check"].as_str().unwrap().to_owned()).collect()
+}
+
+#[test]
+fn skip_leaves_out_a_check_with_a_fix() {
+    assert!(checks(&[]).iter().any(|c| c == "exec-bit"));
+    assert!(!checks(&["--skip", "exec-bit"]).iter().any(|c| c == "exec-bit"));
+    // the same under `--fix`, where `--skip` used to drop only the repair
+    assert!(!checks(&["--fix", "--skip", "exec-bit"]).iter().any(|c| c == "exec-bit"));
+}
+
+#[test]
+fn skip_leaves_out_a_report_only_check() {
+    assert!(checks(&[]).iter().any(|c| c == "modified"));
+    assert!(!checks(&["--skip", "modified"]).iter().any(|c| c == "modified"));
+}
+
+#[test]
+fn skip_uv_drops_it_from_the_report() {
+    assert!(!checks(&[]).iter().any(|c| c == "uv"));
+}
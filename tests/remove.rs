@@ -0,0 +1,24 @@
+//! `uni remove --dry-run` through the built binary.
+
+mod common;
+
+use common::{install, run};
+use std::fs;
+
+#[test]
+fn dry_run_exit_status_says_whether_anything_would_go() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = tmp.path().join("plugins");
+    fs::create_dir_all(&dir).unwrap();
+    install(&dir, "hello", 0o755, serde_json::json!({ "name": "hello", "description": "d", "version": "1" }));
+
+    let out = run(tmp.path(), &dir, &["remove", "--dry-run", "hello"]);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(String::from_utf8_lossy(&out.stdout).lines().filter(|l| l.starts_with('/')).count(), 2);
+    assert!(dir.join("hello").exists() && dir.join("hello.json").exists());
+
+    fs::remove_file(dir.join("hello.json")).unwrap();
+    fs::remove_file(dir.join("hello")).unwrap();
+    let out = run(tmp.path(), &dir, &["remove", "--dry-run", "hello"]);
+    assert_eq!(out.status.code(), Some(0), "{}", String::from_utf8_lossy(&out.stderr));
+}